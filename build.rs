@@ -0,0 +1,31 @@
+use std::env;
+
+/// Forwards the nanomsg version detected by `nanomsg-sys` as the same `cfg` flags,
+/// so that this crate only calls the functions the linked library provides.
+fn main() {
+    println!("cargo:rerun-if-env-changed=DEP_NANOMSG_VERSION");
+    println!("cargo:rerun-if-env-changed=DEP_NANOMSG_RELEASES");
+    println!("cargo:rerun-if-env-changed=DEP_NANOMSG_KNOWN_RELEASES");
+
+    println!("cargo:rustc-check-cfg=cfg(nanomsg_version, values(any()))");
+    for release in releases("DEP_NANOMSG_KNOWN_RELEASES") {
+        println!("cargo:rustc-check-cfg=cfg(nanomsg_{})", release);
+    }
+
+    if let Ok(version) = env::var("DEP_NANOMSG_VERSION") {
+        println!("cargo:rustc-cfg=nanomsg_version=\"{}\"", version);
+    }
+    for release in releases("DEP_NANOMSG_RELEASES") {
+        println!("cargo:rustc-cfg=nanomsg_{}", release);
+    }
+}
+
+/// Reads a comma separated list of `X_Y` releases.
+fn releases(var: &str) -> Vec<String> {
+    env::var(var)
+        .unwrap_or_default()
+        .split(',')
+        .filter(|release| !release.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
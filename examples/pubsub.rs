@@ -9,7 +9,9 @@ use std::time::Duration;
 
 use std::io::{Read, Write};
 
+#[allow(clippy::redundant_static_lifetimes)]
 const CLIENT_DEVICE_URL: &'static str = "ipc:///tmp/pubsub_example_front.ipc";
+#[allow(clippy::redundant_static_lifetimes)]
 const SERVER_DEVICE_URL: &'static str = "ipc:///tmp/pubsub_example_back.ipc";
 
 fn client(topic: &[u8]) {
@@ -1,26 +1,63 @@
+#[cfg(feature = "bindgen")]
+extern crate bindgen;
+#[cfg(feature = "bundled_cc")]
+extern crate cc;
 extern crate cmake;
-extern crate gcc;
 extern crate pkg_config;
 
 use std::env;
+#[cfg(any(feature = "bundled_cc", feature = "bindgen"))]
+use std::fs;
+#[cfg(any(feature = "bundled", feature = "bindgen"))]
+use std::path::{Path, PathBuf};
 #[cfg(feature = "bundled")]
-use std::path::Path;
+use std::process::Command;
 
 #[cfg(feature = "bundled")]
 const NANOMSG_VERSION: &'static str = "1.1.4";
 
+/// Environment variable pointing to a nanomsg source tree to build instead of cloning it.
 #[cfg(feature = "bundled")]
-fn main() {
-    let out_dir = env::var("OUT_DIR").unwrap();
-    let target = env::var("TARGET").unwrap();
+const SRC_DIR_VAR: &str = "NANOMSG_SRC_DIR";
+
+/// Directory of the crate where nanomsg sources can be vendored, see `make vendor`.
+#[cfg(feature = "bundled")]
+const VENDORED_DIR: &str = "nanomsg";
+
+/// Locates the nanomsg sources to build, in order:
+/// the `NANOMSG_SRC_DIR` directory, the vendored sources, and finally a clone from GitHub.
+#[cfg(feature = "bundled")]
+fn source_dir(out_dir: &Path) -> PathBuf {
+    println!("cargo:rerun-if-env-changed={}", SRC_DIR_VAR);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    if let Some(src_dir) = env::var_os(SRC_DIR_VAR) {
+        let src_dir = PathBuf::from(src_dir);
+
+        if !src_dir.join("CMakeLists.txt").exists() {
+            panic!(
+                "{} is set to {}, which does not contain nanomsg sources (no CMakeLists.txt)",
+                SRC_DIR_VAR,
+                src_dir.display()
+            );
+        }
+
+        println!("cargo:rerun-if-changed={}", src_dir.display());
+        return src_dir;
+    }
+
+    let vendored_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join(VENDORED_DIR);
+
+    if vendored_dir.join("CMakeLists.txt").exists() {
+        println!("cargo:rerun-if-changed={}", vendored_dir.display());
+        return vendored_dir;
+    }
 
-    let clone_path = Path::new(&out_dir).join("nanomsg_upstream");
+    let clone_path = out_dir.join("nanomsg_upstream");
 
     // TODO: Determine whether we'd rather always do a fresh clone.
-    // TODO: Determine whether we wouldn't rather use a submodule.
-    if !clone_path.join(".git").exists() {
-        // Panic if we can't clone nanomsg
-        let status = ::std::process::Command::new("git")
+    let (action, status) = if !clone_path.join(".git").exists() {
+        let status = Command::new("git")
             .args(
                 &[
                     "clone",
@@ -32,34 +69,62 @@ fn main() {
                     clone_path.to_str().unwrap(),
                 ],
             )
-            .status()
-            .unwrap();
+            .status();
 
-        if !status.success() {
-            panic!("git clone of nanomsg was not successful");
-        }
+        ("git clone", status)
     } else {
-        let status = ::std::process::Command::new("git")
+        let status = Command::new("git")
             .current_dir(clone_path.clone())
             .args(&["checkout", NANOMSG_VERSION])
-            .status()
-            .unwrap();
+            .status();
 
-        if !status.success() {
-            panic!(
-                "git checkout of nanomsg {} was not successful",
-                NANOMSG_VERSION
-            );
-        }
+        ("git checkout", status)
+    };
+
+    match status {
+        Ok(ref status) if status.success() => clone_path,
+        _ => panic!(
+            "{} of nanomsg {} was not successful. \
+             Without network access, provide the nanomsg sources either by setting {} \
+             to a nanomsg {} source tree, or by vendoring them in {} (see `make vendor`).",
+            action,
+            NANOMSG_VERSION,
+            SRC_DIR_VAR,
+            NANOMSG_VERSION,
+            vendored_dir.display()
+        ),
     }
+}
 
+#[cfg(feature = "bundled")]
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let target = env::var("TARGET").unwrap();
+
+    let src_dir = source_dir(Path::new(&out_dir));
+
+    build_bundled(&src_dir);
+
+    if target.contains("windows") {
+        println!("cargo:rustc-link-lib=mswsock");
+    }
+
+    emit_version(Some(NANOMSG_VERSION));
+
+    #[cfg(feature = "bindgen")]
+    generate_bindings(&src_dir.join("src"));
+}
+
+/// Builds and installs nanomsg with its CMake configuration, which requires cmake.
+#[cfg(all(feature = "bundled", not(feature = "bundled_cc")))]
+fn build_bundled(src_dir: &Path) {
     let getaddrinfo_a_flag = if cfg!(feature = "no_anl") {
         "OFF"
     } else {
         "ON"
     };
 
-    let mut config = cmake::Config::new(clone_path);
+    let mut config = cmake::Config::new(src_dir);
     config
         .define("NN_STATIC_LIB", "ON")
         .define("NN_ENABLE_DOC", "OFF")
@@ -72,26 +137,308 @@ fn main() {
 
     let dst = config.build();
 
+    println!("cargo:rustc-link-search=native={}/lib", dst.display());
+    println!("cargo:rustc-link-search=native={}/lib64", dst.display());
+}
+
+/// Compiles nanomsg with the cc crate and the system C compiler, without cmake,
+/// defining what the CMake configuration would detect for the target.
+#[cfg(feature = "bundled_cc")]
+fn build_bundled(src_dir: &Path) {
+    let target = env::var("TARGET").unwrap();
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap();
+
+    let mut sources = Vec::new();
+    collect_sources(&src_dir.join("src"), &mut sources);
+    sources.sort();
+
+    let mut build = cc::Build::new();
+    build
+        .files(sources)
+        .define("NN_STATIC_LIB", None)
+        .warnings(false);
+
+    for define in platform_defines(&target_os) {
+        build.define(define, None);
+    }
+
+    // Provided by glibc only, musl has neither backtrace nor getaddrinfo_a.
+    if target_env == "gnu" {
+        build.define("NN_HAVE_BACKTRACE", None);
+
+        // Same condition as the link to libanl in lib.rs.
+        if target_os == "linux" && !cfg!(feature = "no_anl") {
+            build.define("NN_HAVE_GETADDRINFO_A", None);
+        }
+    }
+
     if target.contains("windows") {
-        println!("cargo:rustc-link-lib=mswsock");
+        // Condition variables and other APIs used by nanomsg require Windows Vista.
+        build.define("_WIN32_WINNT", "0x0600");
     }
 
-    println!("cargo:rustc-link-search=native={}/lib", dst.display());
-    println!("cargo:rustc-link-search=native={}/lib64", dst.display());
+    // Links libnanomsg.a statically.
+    build.compile("nanomsg");
+
+    if target.contains("windows") {
+        println!("cargo:rustc-link-lib=advapi32");
+    }
+}
+
+/// Collects the C files of the nanomsg `src` directory.
+/// Platform specific code lives in `.inc` files, included by the C files depending on the defines.
+#[cfg(feature = "bundled_cc")]
+fn collect_sources(dir: &Path, sources: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            collect_sources(&path, sources);
+        } else if path.extension().map_or(false, |ext| ext == "c") {
+            sources.push(path);
+        }
+    }
+}
+
+/// The defines the nanomsg CMake configuration would detect for the target platform,
+/// selecting in particular the poller (epoll, kqueue, poll or IOCP) and the event notification mechanism.
+#[cfg(feature = "bundled_cc")]
+fn platform_defines(target_os: &str) -> Vec<&'static str> {
+    let mut defines = match target_os {
+        "windows" => {
+            return vec![
+                "NN_HAVE_WINDOWS",
+                "NN_USE_WINSOCK",
+                "_CRT_SECURE_NO_WARNINGS",
+            ]
+        }
+        "linux" => vec![
+            "NN_HAVE_LINUX",
+            "NN_USE_EPOLL",
+            "NN_USE_EVENTFD",
+            "NN_HAVE_EVENTFD",
+            "NN_HAVE_PIPE2",
+            "NN_HAVE_ACCEPT4",
+            "NN_HAVE_SEMAPHORE",
+            "_GNU_SOURCE",
+        ],
+        "android" => vec![
+            "NN_HAVE_ANDROID",
+            "NN_USE_EPOLL",
+            "NN_USE_EVENTFD",
+            "NN_HAVE_EVENTFD",
+            "NN_HAVE_PIPE2",
+            "NN_HAVE_ACCEPT4",
+            "NN_HAVE_SEMAPHORE",
+            "_GNU_SOURCE",
+        ],
+        "macos" | "ios" => vec!["NN_HAVE_OSX", "NN_USE_KQUEUE", "NN_USE_PIPE"],
+        "freebsd" | "dragonfly" => vec![
+            "NN_HAVE_FREEBSD",
+            "NN_USE_KQUEUE",
+            "NN_USE_PIPE",
+            "NN_HAVE_PIPE2",
+            "NN_HAVE_ACCEPT4",
+            "NN_HAVE_SEMAPHORE",
+        ],
+        "netbsd" => vec![
+            "NN_HAVE_NETBSD",
+            "NN_USE_KQUEUE",
+            "NN_USE_PIPE",
+            "NN_HAVE_SEMAPHORE",
+        ],
+        "openbsd" => vec![
+            "NN_HAVE_OPENBSD",
+            "NN_USE_KQUEUE",
+            "NN_USE_PIPE",
+            "NN_HAVE_SEMAPHORE",
+        ],
+        "solaris" | "illumos" => vec![
+            "NN_HAVE_SOLARIS",
+            "NN_USE_POLL",
+            "NN_USE_PIPE",
+            "NN_HAVE_SEMAPHORE",
+        ],
+        _ => vec!["NN_USE_POLL", "NN_USE_PIPE", "NN_HAVE_SEMAPHORE"],
+    };
+
+    // Available on every supported POSIX system.
+    defines.extend(&[
+        "NN_HAVE_POLL",
+        "NN_HAVE_PIPE",
+        "NN_HAVE_SOCKETPAIR",
+        "NN_HAVE_UNIX_SOCKETS",
+        "NN_HAVE_MSG_CONTROL",
+        "NN_HAVE_CLOCK_MONOTONIC",
+        "NN_HAVE_CLOCK_GETTIME",
+        "NN_HAVE_GCC_ATOMIC_BUILTINS",
+    ]);
+    defines
 }
 
 #[cfg(not(feature = "bundled"))]
 fn main() {
     // Attempt to use pkg_config to locate nanomsg (search location can be set via environment)
-    if pkg_config::find_library("nanomsg").is_err() {
-        // If that failed we have some reasonable looking defaults.
-        let target = env::var("TARGET").unwrap();
-        let windows = target.contains("windows");
-        if windows {
-            println!("cargo:rustc-link-lib=nanomsg");
-            println!("cargo:rustc-link-search=C:/Program Files (x86)/nanomsg/lib");
-        } else {
-            println!("cargo:rustc-flags=-L /usr/local/lib -l nanomsg");
+    match pkg_config::find_library("nanomsg") {
+        Ok(library) => {
+            emit_version(Some(&library.version));
+
+            #[cfg(feature = "bindgen")]
+            generate_bindings(&system_header_dir(&library.include_paths));
+        }
+        Err(_) => {
+            // If that failed we have some reasonable looking defaults.
+            let target = env::var("TARGET").unwrap();
+            let windows = target.contains("windows");
+            if windows {
+                println!("cargo:rustc-link-lib=nanomsg");
+                println!("cargo:rustc-link-search=C:/Program Files (x86)/nanomsg/lib");
+            } else {
+                println!("cargo:rustc-flags=-L /usr/local/lib -l nanomsg");
+            }
+            emit_version(None);
+
+            #[cfg(feature = "bindgen")]
+            generate_bindings(&system_header_dir(&[]));
+        }
+    }
+}
+
+/// The public headers of nanomsg, installed in the `nanomsg` include directory.
+#[cfg(feature = "bindgen")]
+const HEADERS: &[&str] = &[
+    "nn.h", "bus.h", "inproc.h", "ipc.h", "pair.h", "pipeline.h", "pubsub.h", "reqrep.h", "survey.h", "tcp.h",
+    "ws.h",
+];
+
+/// Error codes nanomsg defines when the system does not.
+#[cfg(feature = "bindgen")]
+const ERRORS: &str = "E(NOTSUP|PROTONOSUPPORT|NOBUFS|NETDOWN|ADDRINUSE|ADDRNOTAVAIL|CONNREFUSED|INPROGRESS|NOTSOCK|\
+                      AFNOSUPPORT|PROTO|AGAIN|BADF|INVAL|MFILE|FAULT|ACCESS?|NETRESET|NETUNREACH|HOSTUNREACH|\
+                      NOTCONN|MSGSIZE|TIMEDOUT|CONNABORTED|CONNRESET|NOPROTOOPT|ISCONN|SOCKTNOSUPPORT|TERM|FSM)";
+
+/// Finds the directory holding the installed nanomsg headers.
+#[cfg(all(feature = "bindgen", not(feature = "bundled")))]
+fn system_header_dir(include_paths: &[PathBuf]) -> PathBuf {
+    let defaults = [PathBuf::from("/usr/local/include"), PathBuf::from("/usr/include")];
+    let candidates: Vec<PathBuf> = include_paths
+        .iter()
+        .chain(defaults.iter())
+        .map(|dir| dir.join("nanomsg"))
+        .collect();
+
+    match candidates.iter().find(|dir| dir.join("nn.h").exists()) {
+        Some(dir) => dir.clone(),
+        None => panic!(
+            "the bindgen feature requires the nanomsg headers, nanomsg/nn.h was not found in {:?}",
+            candidates
+        ),
+    }
+}
+
+/// Generates the FFI layer from the headers in `header_dir`, into `$OUT_DIR/bindings.rs`.
+/// The structures are the hand-written ones, so that the function signatures can be compared.
+/// Also writes `$OUT_DIR/generated_names.rs`, listing the generated constants and functions for the tests.
+#[cfg(feature = "bindgen")]
+fn generate_bindings(header_dir: &Path) {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let wrapper: String = HEADERS
+        .iter()
+        .map(|header| format!("#include \"{}\"\n", header_dir.join(header).display()))
+        .collect();
+
+    for header in HEADERS {
+        println!("cargo:rerun-if-changed={}", header_dir.join(header).display());
+    }
+
+    let bindings = bindgen::Builder::default()
+        .header_contents("nanomsg_wrapper.h", &wrapper)
+        .allowlist_function("nn_.*")
+        .allowlist_var("NN_.*|AF_SP.*|PROTO_SP|SP_HDR")
+        .allowlist_var(ERRORS)
+        .blocklist_type("nn_(pollfd|iovec|msghdr|cmsghdr|symbol_properties)")
+        .raw_line("use super::{nn_cmsghdr, nn_msghdr, nn_pollfd, nn_symbol_properties};")
+        .default_macro_constant_type(bindgen::MacroTypeVariation::Signed)
+        .layout_tests(false)
+        .generate()
+        .expect("unable to generate the nanomsg bindings");
+    let source = bindings.to_string();
+
+    let mut constants = String::new();
+    let mut functions = String::new();
+
+    for line in source.lines().map(str::trim) {
+        if line.starts_with("pub const ") {
+            let name = line["pub const ".len()..].split(':').next().unwrap();
+            constants.push_str(&format!("    (\"{0}\", generated::{0} as i64),\n", name));
+        } else if line.starts_with("pub fn ") {
+            let name = line["pub fn ".len()..].split('(').next().unwrap();
+            functions.push_str(&format!("    \"{}\",\n", name));
         }
     }
+
+    fs::write(out_dir.join("bindings.rs"), source).unwrap();
+    fs::write(
+        out_dir.join("generated_names.rs"),
+        format!(
+            "pub const CONSTANTS: &[(&str, i64)] = &[\n{}];\n\npub const FUNCTIONS: &[&str] = &[\n{}];\n",
+            constants, functions
+        ),
+    )
+    .unwrap();
+}
+
+/// Releases that can be tested with `#[cfg(nanomsg_X_Y)]`,
+/// the flag being set when the detected version is X.Y or later.
+const KNOWN_RELEASES: &[(u32, u32)] = &[
+    (0, 4), (0, 5), (0, 6), (0, 7), (0, 8), (0, 9), (1, 0), (1, 1), (1, 2)
+];
+
+/// Exposes the detected nanomsg version, if any:
+/// - `cfg(nanomsg_version = "X.Y.Z")` and `cfg(nanomsg_X_Y)` flags for this crate,
+/// - the `NANOMSG_SYS_DETECTED_VERSION` compile time variable, see `NANOMSG_VERSION`,
+/// - the `DEP_NANOMSG_VERSION` and `DEP_NANOMSG_RELEASES` variables for the build scripts of dependent crates,
+///   the latter listing the `X_Y` flags that are set, and `DEP_NANOMSG_KNOWN_RELEASES` all the possible ones.
+fn emit_version(version: Option<&str>) {
+    let known: Vec<String> = KNOWN_RELEASES
+        .iter()
+        .map(|&(major, minor)| format!("{}_{}", major, minor))
+        .collect();
+
+    println!("cargo:rustc-check-cfg=cfg(nanomsg_version, values(any()))");
+    for release in &known {
+        println!("cargo:rustc-check-cfg=cfg(nanomsg_{})", release);
+    }
+    println!("cargo:known_releases={}", known.join(","));
+
+    let version = match version {
+        Some(version) => version,
+        None => return,
+    };
+
+    println!("cargo:rustc-cfg=nanomsg_version=\"{}\"", version);
+    println!("cargo:rustc-env=NANOMSG_SYS_DETECTED_VERSION={}", version);
+    println!("cargo:version={}", version);
+
+    let detected = parse_version(version);
+    let releases: Vec<String> = KNOWN_RELEASES
+        .iter()
+        .filter(|&&release| Some(release) <= detected)
+        .map(|&(major, minor)| format!("{}_{}", major, minor))
+        .collect();
+
+    for release in &releases {
+        println!("cargo:rustc-cfg=nanomsg_{}", release);
+    }
+    println!("cargo:releases={}", releases.join(","));
+}
+
+/// Extracts the major and minor numbers of versions like `1.1.4` or `1.1.5-rc1`.
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut numbers = version.split(|c: char| !c.is_ascii_digit());
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next()?.parse().ok()?;
+
+    Some((major, minor))
 }
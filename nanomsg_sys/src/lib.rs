@@ -62,9 +62,65 @@ pub const NN_DONTWAIT: c_int = 1;
 pub const NN_INPROC: c_int = -1;
 pub const NN_IPC: c_int = -2;
 pub const NN_TCP: c_int = -3;
+pub const NN_WS: c_int = -4;
 
 pub const NN_TCP_NODELAY: c_int = 1;
 
+pub const NN_WS_MSG_TYPE: c_int = 1;
+pub const NN_WS_MSG_TYPE_TEXT: c_int = 0x01;
+pub const NN_WS_MSG_TYPE_BINARY: c_int = 0x02;
+
+pub const NN_STAT_ESTABLISHED_CONNECTIONS: c_int = 101;
+pub const NN_STAT_ACCEPTED_CONNECTIONS: c_int = 102;
+pub const NN_STAT_DROPPED_CONNECTIONS: c_int = 103;
+pub const NN_STAT_BROKEN_CONNECTIONS: c_int = 104;
+pub const NN_STAT_CONNECT_ERRORS: c_int = 105;
+pub const NN_STAT_BIND_ERRORS: c_int = 106;
+pub const NN_STAT_ACCEPT_ERRORS: c_int = 107;
+pub const NN_STAT_CURRENT_CONNECTIONS: c_int = 201;
+pub const NN_STAT_INPROGRESS_CONNECTIONS: c_int = 202;
+pub const NN_STAT_CURRENT_EP_ERRORS: c_int = 203;
+pub const NN_STAT_MESSAGES_SENT: c_int = 301;
+pub const NN_STAT_MESSAGES_RECEIVED: c_int = 302;
+pub const NN_STAT_BYTES_SENT: c_int = 303;
+pub const NN_STAT_BYTES_RECEIVED: c_int = 304;
+pub const NN_STAT_CURRENT_SND_PRIORITY: c_int = 401;
+
+pub const NN_NS_NAMESPACE: c_int = 0;
+pub const NN_NS_VERSION: c_int = 1;
+pub const NN_NS_DOMAIN: c_int = 2;
+pub const NN_NS_TRANSPORT: c_int = 3;
+pub const NN_NS_PROTOCOL: c_int = 4;
+pub const NN_NS_OPTION_LEVEL: c_int = 5;
+pub const NN_NS_SOCKET_OPTION: c_int = 6;
+pub const NN_NS_TRANSPORT_OPTION: c_int = 7;
+pub const NN_NS_OPTION_TYPE: c_int = 8;
+pub const NN_NS_OPTION_UNIT: c_int = 9;
+pub const NN_NS_FLAG: c_int = 10;
+pub const NN_NS_ERROR: c_int = 11;
+pub const NN_NS_LIMIT: c_int = 12;
+pub const NN_NS_EVENT: c_int = 13;
+pub const NN_NS_STATISTIC: c_int = 14;
+
+pub const NN_TYPE_NONE: c_int = 0;
+pub const NN_TYPE_INT: c_int = 1;
+pub const NN_TYPE_STR: c_int = 2;
+
+pub const NN_UNIT_NONE: c_int = 0;
+pub const NN_UNIT_BYTES: c_int = 1;
+pub const NN_UNIT_MILLISECONDS: c_int = 2;
+pub const NN_UNIT_PRIORITY: c_int = 3;
+pub const NN_UNIT_BOOLEAN: c_int = 4;
+pub const NN_UNIT_MESSAGES: c_int = 5;
+pub const NN_UNIT_COUNTER: c_int = 6;
+
+/// The nanomsg release detected when building this crate, either by pkg-config or from the bundled sources.
+/// `None` when the library was searched in the default locations.
+pub const NANOMSG_VERSION: Option<&str> = option_env!("NANOMSG_SYS_DETECTED_VERSION");
+
+pub const PROTO_SP: c_int = 1;
+pub const SP_HDR: c_int = 1;
+
 pub const NN_POLLIN: c_short = 1;
 pub const NN_POLLOUT: c_short = 2;
 pub const NN_POLL_IN_AND_OUT: c_short = NN_POLLIN + NN_POLLOUT;
@@ -164,6 +220,69 @@ impl nn_pollfd {
     }
 }
 
+/// Scatter/gather array element, used by `nn_sendmsg` and `nn_recvmsg`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct nn_iovec {
+    pub iov_base: *mut c_void,
+    pub iov_len: size_t
+}
+
+/// Message header, used by `nn_sendmsg` and `nn_recvmsg`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct nn_msghdr {
+    pub msg_iov: *mut nn_iovec,
+    pub msg_iovlen: c_int,
+    pub msg_control: *mut c_void,
+    pub msg_controllen: size_t
+}
+
+/// Ancillary data property header, found in the control part of a message.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct nn_cmsghdr {
+    pub cmsg_len: size_t,
+    pub cmsg_level: c_int,
+    pub cmsg_type: c_int
+}
+
+/// Properties of a symbol, filled by `nn_symbol_info`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct nn_symbol_properties {
+    /// The constant value.
+    pub value: c_int,
+    /// The constant name, a static null-terminated string.
+    pub name: *const c_char,
+    /// The namespace the symbol belongs to, one of `NN_NS_*`.
+    pub ns: c_int,
+    /// The option type for socket and transport options, one of `NN_TYPE_*`.
+    pub type_: c_int,
+    /// The unit of the option value, one of `NN_UNIT_*`.
+    pub unit: c_int
+}
+
+/// Rounds `len` up to the alignment of ancillary data properties.
+pub const fn NN_CMSG_ALIGN_(len: size_t) -> size_t {
+    (len + std::mem::size_of::<size_t>() - 1) & !(std::mem::size_of::<size_t>() - 1)
+}
+
+/// Number of bytes occupied by a property with a payload of `len` bytes, including padding.
+pub const fn NN_CMSG_SPACE(len: size_t) -> size_t {
+    NN_CMSG_ALIGN_(len) + NN_CMSG_ALIGN_(std::mem::size_of::<nn_cmsghdr>())
+}
+
+/// Value to store in the `cmsg_len` field of a property with a payload of `len` bytes.
+pub const fn NN_CMSG_LEN(len: size_t) -> size_t {
+    NN_CMSG_ALIGN_(std::mem::size_of::<nn_cmsghdr>()) + len
+}
+
+/// Returns a pointer to the payload of the property.
+pub unsafe fn NN_CMSG_DATA(cmsg: *const nn_cmsghdr) -> *const u8 {
+    (cmsg as *const u8).add(NN_CMSG_ALIGN_(std::mem::size_of::<nn_cmsghdr>()))
+}
+
 #[cfg_attr(all(target_os = "linux", feature = "bundled", not(feature = "no_anl")), link(name = "anl"))]
 #[cfg_attr(feature = "bundled", link(name = "nanomsg", kind = "static"))]
 extern {
@@ -258,10 +377,10 @@ extern {
     pub fn nn_recv(socket: c_int, buf: *mut c_void, len: size_t, flags: c_int) -> c_int;
 
     /// http://nanomsg.org/v0.4/nn_sendmsg.3.html
-    pub fn nn_sendmsg(socket: c_int, msghdr: *const c_void, flags: c_int) -> c_int;
+    pub fn nn_sendmsg(socket: c_int, msghdr: *const nn_msghdr, flags: c_int) -> c_int;
 
     /// http://nanomsg.org/v0.4/nn_recvmsg.3.html
-    pub fn nn_recvmsg(socket: c_int, msghdr: *mut c_void, flags: c_int) -> c_int;
+    pub fn nn_recvmsg(socket: c_int, msghdr: *mut nn_msghdr, flags: c_int) -> c_int;
 
     /// http://nanomsg.org/v0.4/nn_allocmsg.3.html
     pub fn nn_allocmsg(size: size_t, ty: c_int) -> *mut c_void;
@@ -282,12 +401,41 @@ extern {
     pub fn nn_strerror(errnum: c_int) -> *const c_char;
 
     /// http://nanomsg.org/v0.4/nn_term.3.html
-    pub fn nn_term() -> c_void;
+    pub fn nn_term();
 
     /// http://nanomsg.org/v0.4/nn_device.3.html
     pub fn nn_device(socket1: c_int, socket2: c_int) -> c_int;
 
+    /// "Retrieves the symbols exposed by the library. The index is a zero-based index
+    /// of the symbol, its value is stored in the location pointed to by value.
+    /// Returns the name of the symbol, or null once index is out of range."
+    ///
+    /// http://nanomsg.org/v1.0.0/nn_symbol.3.html
     pub fn nn_symbol(index: c_int, value: *mut c_int) -> *const c_char;
+
+    /// "Retrieves the symbol information for the symbol at the zero-based index,
+    /// filling up to buflen bytes of the buffer. Returns the number of bytes
+    /// written, or zero once index is out of range."
+    ///
+    /// Available since nanomsg 0.4, or when the version of the library could not be detected.
+    ///
+    /// http://nanomsg.org/v1.0.0/nn_symbol_info.3.html
+    #[cfg(any(nanomsg_0_4, not(nanomsg_version)))]
+    pub fn nn_symbol_info(index: c_int, buf: *mut nn_symbol_properties, buflen: c_int) -> c_int;
+
+    /// Retrieves the value of the `NN_STAT_*` statistic of the socket.
+    /// Returns `u64::MAX` ((uint64_t)-1) and sets errno in case of error.
+    /// Available since nanomsg 0.5, or when the version of the library could not be detected.
+    #[cfg(any(nanomsg_0_5, not(nanomsg_version)))]
+    pub fn nn_get_statistic(socket: c_int, stat: c_int) -> u64;
+}
+
+/// The FFI layer generated by bindgen from the nanomsg headers, when the `bindgen` feature is enabled.
+/// The hand-written bindings remain the API of this crate, the tests check them against this module.
+#[cfg(feature = "bindgen")]
+#[allow(non_upper_case_globals, non_camel_case_types, non_snake_case, dead_code, unused_imports)]
+pub mod generated {
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
 #[cfg(test)]
@@ -380,6 +528,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_send_and_receive_scattered_msg() {
+
+        let url = get_url("ipc:///tmp/should_send_and_receive_scattered_msg.ipc");
+
+        let push_sock = test_create_socket(AF_SP, NN_PUSH);
+        let push_endpoint = test_bind(push_sock, url.as_ptr() as *const i8);
+
+        let pull_sock = test_create_socket(AF_SP, NN_PULL);
+        let pull_endpoint = test_connect(pull_sock, url.as_ptr() as *const i8);
+
+        let header = b"foo";
+        let body = b"bar";
+        let mut send_iov = [
+            nn_iovec { iov_base: header.as_ptr() as *mut c_void, iov_len: header.len() },
+            nn_iovec { iov_base: body.as_ptr() as *mut c_void, iov_len: body.len() }
+        ];
+        let send_hdr = nn_msghdr {
+            msg_iov: send_iov.as_mut_ptr(),
+            msg_iovlen: 2,
+            msg_control: ptr::null_mut(),
+            msg_controllen: 0
+        };
+        assert_eq!(6, unsafe { nn_sendmsg(push_sock, &send_hdr, 0) });
+
+        let mut first = [0u8; 4];
+        let mut second = [0u8; 2];
+        let mut recv_iov = [
+            nn_iovec { iov_base: first.as_mut_ptr() as *mut c_void, iov_len: first.len() },
+            nn_iovec { iov_base: second.as_mut_ptr() as *mut c_void, iov_len: second.len() }
+        ];
+        let mut recv_hdr = nn_msghdr {
+            msg_iov: recv_iov.as_mut_ptr(),
+            msg_iovlen: 2,
+            msg_control: ptr::null_mut(),
+            msg_controllen: 0
+        };
+        assert_eq!(6, unsafe { nn_recvmsg(pull_sock, &mut recv_hdr, 0) });
+        assert_eq!(b"foob", &first);
+        assert_eq!(b"ar", &second);
+
+        unsafe {
+            nn_shutdown(pull_sock, pull_endpoint);
+            nn_close(pull_sock);
+            nn_shutdown(push_sock, push_endpoint);
+            nn_close(push_sock);
+        }
+    }
+
     #[test]
     fn should_create_a_pair() {
 
@@ -677,18 +874,67 @@ mod tests {
             "NN_IPV4ONLY" => Some(NN_IPV4ONLY),
             "NN_SOCKET_NAME" => Some(NN_SOCKET_NAME),
             "NN_RCVMAXSIZE" => Some(NN_RCVMAXSIZE),
+            "NN_MAXTTL" => Some(NN_MAXTTL),
             "NN_DONTWAIT" => Some(NN_DONTWAIT),
+            "NN_POLLIN" => Some(NN_POLLIN as c_int),
+            "NN_POLLOUT" => Some(NN_POLLOUT as c_int),
+            "PROTO_SP" => Some(PROTO_SP),
+            "SP_HDR" => Some(SP_HDR),
             "NN_INPROC" => Some(NN_INPROC),
             "NN_IPC" => Some(NN_IPC),
             "NN_TCP" => Some(NN_TCP),
             "NN_TCP_NODELAY" => Some(NN_TCP_NODELAY),
+            "NN_WS" => Some(NN_WS),
+            "NN_WS_MSG_TYPE" => Some(NN_WS_MSG_TYPE),
+            "NN_WS_MSG_TYPE_TEXT" => Some(NN_WS_MSG_TYPE_TEXT),
+            "NN_WS_MSG_TYPE_BINARY" => Some(NN_WS_MSG_TYPE_BINARY),
+            "NN_STAT_ESTABLISHED_CONNECTIONS" => Some(NN_STAT_ESTABLISHED_CONNECTIONS),
+            "NN_STAT_ACCEPTED_CONNECTIONS" => Some(NN_STAT_ACCEPTED_CONNECTIONS),
+            "NN_STAT_DROPPED_CONNECTIONS" => Some(NN_STAT_DROPPED_CONNECTIONS),
+            "NN_STAT_BROKEN_CONNECTIONS" => Some(NN_STAT_BROKEN_CONNECTIONS),
+            "NN_STAT_CONNECT_ERRORS" => Some(NN_STAT_CONNECT_ERRORS),
+            "NN_STAT_BIND_ERRORS" => Some(NN_STAT_BIND_ERRORS),
+            "NN_STAT_ACCEPT_ERRORS" => Some(NN_STAT_ACCEPT_ERRORS),
+            "NN_STAT_CURRENT_CONNECTIONS" => Some(NN_STAT_CURRENT_CONNECTIONS),
+            "NN_STAT_INPROGRESS_CONNECTIONS" => Some(NN_STAT_INPROGRESS_CONNECTIONS),
+            "NN_STAT_CURRENT_EP_ERRORS" => Some(NN_STAT_CURRENT_EP_ERRORS),
+            "NN_STAT_MESSAGES_SENT" => Some(NN_STAT_MESSAGES_SENT),
+            "NN_STAT_MESSAGES_RECEIVED" => Some(NN_STAT_MESSAGES_RECEIVED),
+            "NN_STAT_BYTES_SENT" => Some(NN_STAT_BYTES_SENT),
+            "NN_STAT_BYTES_RECEIVED" => Some(NN_STAT_BYTES_RECEIVED),
+            "NN_STAT_CURRENT_SND_PRIORITY" => Some(NN_STAT_CURRENT_SND_PRIORITY),
+            "NN_NS_NAMESPACE" => Some(NN_NS_NAMESPACE),
+            "NN_NS_VERSION" => Some(NN_NS_VERSION),
+            "NN_NS_DOMAIN" => Some(NN_NS_DOMAIN),
+            "NN_NS_TRANSPORT" => Some(NN_NS_TRANSPORT),
+            "NN_NS_PROTOCOL" => Some(NN_NS_PROTOCOL),
+            "NN_NS_OPTION_LEVEL" => Some(NN_NS_OPTION_LEVEL),
+            "NN_NS_SOCKET_OPTION" => Some(NN_NS_SOCKET_OPTION),
+            "NN_NS_TRANSPORT_OPTION" => Some(NN_NS_TRANSPORT_OPTION),
+            "NN_NS_OPTION_TYPE" => Some(NN_NS_OPTION_TYPE),
+            "NN_NS_OPTION_UNIT" => Some(NN_NS_OPTION_UNIT),
+            "NN_NS_FLAG" => Some(NN_NS_FLAG),
+            "NN_NS_ERROR" => Some(NN_NS_ERROR),
+            "NN_NS_LIMIT" => Some(NN_NS_LIMIT),
+            "NN_NS_EVENT" => Some(NN_NS_EVENT),
+            "NN_NS_STATISTIC" => Some(NN_NS_STATISTIC),
+            "NN_TYPE_NONE" => Some(NN_TYPE_NONE),
+            "NN_TYPE_INT" => Some(NN_TYPE_INT),
+            "NN_TYPE_STR" => Some(NN_TYPE_STR),
+            "NN_UNIT_NONE" => Some(NN_UNIT_NONE),
+            "NN_UNIT_BYTES" => Some(NN_UNIT_BYTES),
+            "NN_UNIT_MILLISECONDS" => Some(NN_UNIT_MILLISECONDS),
+            "NN_UNIT_PRIORITY" => Some(NN_UNIT_PRIORITY),
+            "NN_UNIT_BOOLEAN" => Some(NN_UNIT_BOOLEAN),
+            "NN_UNIT_MESSAGES" => Some(NN_UNIT_MESSAGES),
+            "NN_UNIT_COUNTER" => Some(NN_UNIT_COUNTER),
             "ETERM" => Some(ETERM),
             "EFSM" => Some(EFSM),
             "ENAMETOOLONG" => Some(ENAMETOOLONG),
             "ENODEV" => Some(ENODEV),
             "EINTR" => Some(EINTR),
             "NN_HAUSNUMERO" => Some(NN_HAUSNUMERO),
-            "ENOTSUP " => Some(ENOTSUP ),
+            "ENOTSUP" => Some(ENOTSUP),
             "EPROTONOSUPPORT" => Some(EPROTONOSUPPORT),
             "ENOBUFS" => Some(ENOBUFS),
             "ENETDOWN" => Some(ENETDOWN),
@@ -698,12 +944,13 @@ mod tests {
             "EINPROGRESS" => Some(EINPROGRESS),
             "ENOTSOCK" => Some(ENOTSOCK),
             "EAFNOSUPPORT" => Some(EAFNOSUPPORT),
-            "EPROTO " => Some(EPROTO ),
+            "EPROTO" => Some(EPROTO),
             "EAGAIN" => Some(EAGAIN),
             "EBADF" => Some(EBADF),
             "EINVAL" => Some(EINVAL),
             "EMFILE" => Some(EMFILE),
             "EFAULT" => Some(EFAULT),
+            "EACCES" => Some(EACCESS),
             "EACCESS" => Some(EACCESS),
             "ENETRESET" => Some(ENETRESET),
             "ENETUNREACH" => Some(ENETUNREACH),
@@ -719,4 +966,62 @@ mod tests {
             _ => None
         }
     }
+
+    #[cfg(feature = "bindgen")]
+    mod generated_bindings {
+        use super::get_constant_value_by_name;
+        use ::generated;
+
+        include!(concat!(env!("OUT_DIR"), "/generated_names.rs"));
+
+        /// Items of the headers that are deliberately not bound by hand.
+        const UNBOUND: &[&str] = &[
+            // Read at runtime through nn_symbol_info instead.
+            "NN_VERSION_CURRENT", "NN_VERSION_REVISION", "NN_VERSION_AGE",
+            // Windows only options of the IPC transport.
+            "NN_IPC_SEC_ATTR", "NN_IPC_OUTBUFSZ", "NN_IPC_INBUFSZ",
+            // Implementation detail of the NN_CMSG_NXTHDR macro.
+            "nn_cmsg_nxthdr_"
+        ];
+
+        /// Fails to compile unless each hand-written function has the signature of the generated one,
+        /// the two function items being coerced to a common function pointer type.
+        /// Evaluates to the names of the functions.
+        macro_rules! same_signatures {
+            ($($name:ident),*) => {{
+                $(let _ = if true { ::$name } else { generated::$name };)*
+                [$(stringify!($name)),*]
+            }}
+        }
+
+        #[test]
+        fn generated_constants_match_the_checked_in_ones() {
+            let mut mismatches = Vec::new();
+
+            for &(name, value) in CONSTANTS {
+                match get_constant_value_by_name(name) {
+                    Some(checked_in) if checked_in as i64 == value => {},
+                    Some(checked_in) => mismatches.push(format!("{} is {} in the headers but {}", name, value, checked_in)),
+                    None if UNBOUND.contains(&name) => {},
+                    None => mismatches.push(format!("{} is not bound", name))
+                }
+            }
+
+            assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+        }
+
+        #[test]
+        fn generated_functions_match_the_checked_in_ones() {
+            let checked_in = same_signatures!(
+                nn_socket, nn_close, nn_setsockopt, nn_getsockopt, nn_bind, nn_connect, nn_shutdown,
+                nn_send, nn_recv, nn_sendmsg, nn_recvmsg, nn_allocmsg, nn_reallocmsg, nn_freemsg,
+                nn_poll, nn_errno, nn_strerror, nn_term, nn_device, nn_symbol, nn_symbol_info,
+                nn_get_statistic
+            );
+
+            for name in FUNCTIONS {
+                assert!(checked_in.contains(name) || UNBOUND.contains(name), "{} is not bound", name);
+            }
+        }
+    }
 }
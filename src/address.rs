@@ -0,0 +1,410 @@
+//! Typed endpoint addresses.
+//!
+//! `Socket::bind` and `Socket::connect` accept either an `Address` or its string representation,
+//! which is parsed and validated before being handed to nanomsg,
+//! so that malformed addresses are reported with a precise error.
+
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::result::{Error, Result};
+use crate::Transport;
+
+/// The address of an endpoint, made of a transport and a transport-specific part.
+///
+/// # Example
+///
+/// ```rust
+/// use nanomsg::Address;
+///
+/// let addr: Address = "tcp://eth0;10.0.0.1:5555".parse().unwrap();
+///
+/// assert_eq!(
+///     addr,
+///     Address::Tcp {
+///         interface: Some("eth0".to_owned()),
+///         host: "10.0.0.1".to_owned(),
+///         port: 5555,
+///     }
+/// );
+/// assert_eq!("tcp://eth0;10.0.0.1:5555", addr.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// `inproc://name`, an in-process endpoint identified by its name.
+    Inproc(String),
+    /// `ipc://path`, an inter-process endpoint, a file path on POSIX systems or a named pipe on Windows.
+    Ipc(String),
+    /// `tcp://[interface;]host:port`
+    ///
+    /// When binding, the host is the local interface name, IP address or `*`.
+    /// When connecting, the host is the remote host name or IP address
+    /// and the interface, if any, is the local interface to connect from.
+    Tcp {
+        interface: Option<String>,
+        host: String,
+        port: u16,
+    },
+    /// `ws://[interface;]host:port[/path]`, same as `Tcp` with an optional resource path.
+    /// The path is either empty or starts with `/`.
+    Ws {
+        interface: Option<String>,
+        host: String,
+        port: u16,
+        path: String,
+    },
+}
+
+const INPROC_SCHEME: &str = "inproc";
+const IPC_SCHEME: &str = "ipc";
+const TCP_SCHEME: &str = "tcp";
+const WS_SCHEME: &str = "ws";
+
+impl Address {
+    /// Returns the transport of the address.
+    pub fn transport(&self) -> Transport {
+        match *self {
+            Address::Inproc(_) => Transport::Inproc,
+            Address::Ipc(_) => Transport::Ipc,
+            Address::Tcp { .. } => Transport::Tcp,
+            Address::Ws { .. } => Transport::Ws,
+        }
+    }
+
+    /// Checks the transport specific syntax of the address.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : A part of the address is empty or malformed.
+    /// - `NameTooLong` : The address is longer than `NN_SOCKADDR_MAX - 1` bytes.
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            Address::Inproc(ref name) => check_not_empty(name)?,
+            Address::Ipc(ref path) => check_not_empty(path)?,
+            Address::Tcp {
+                ref interface,
+                ref host,
+                ..
+            } => check_host(interface, host)?,
+            Address::Ws {
+                ref interface,
+                ref host,
+                ref path,
+                ..
+            } => {
+                check_host(interface, host)?;
+
+                if !path.is_empty() && !path.starts_with('/') {
+                    return Err(Error::InvalidInput);
+                }
+            }
+        }
+
+        let len = self.to_string().len();
+
+        if len >= nanomsg_sys::NN_SOCKADDR_MAX as usize {
+            return Err(Error::NameTooLong);
+        }
+
+        Ok(())
+    }
+
+    /// Validates the address and converts it into the string expected by `nn_bind` and `nn_connect`.
+    pub(crate) fn to_c_string(&self) -> Result<CString> {
+        self.validate()?;
+
+        CString::new(self.to_string()).map_err(|_| Error::InvalidInput)
+    }
+}
+
+fn check_not_empty(part: &str) -> Result<()> {
+    if part.is_empty() || part.contains('\0') {
+        Err(Error::InvalidInput)
+    } else {
+        Ok(())
+    }
+}
+
+fn check_host(interface: &Option<String>, host: &str) -> Result<()> {
+    if let Some(ref interface) = *interface {
+        check_not_empty(interface)?;
+
+        if interface.contains(';') {
+            return Err(Error::InvalidInput);
+        }
+    }
+
+    check_not_empty(host)?;
+
+    let bracketed = host.starts_with('[') || host.ends_with(']');
+    let well_bracketed = host.len() > 2 && host.starts_with('[') && host.ends_with(']');
+
+    if host.contains(';') || host.contains('/') || (bracketed && !well_bracketed) {
+        return Err(Error::InvalidInput);
+    }
+
+    Ok(())
+}
+
+/// Splits `[interface;]host:port` into its parts.
+fn parse_host_port(rest: &str) -> Result<(Option<String>, String, u16)> {
+    let (interface, host_port) = match rest.find(';') {
+        Some(index) => (Some(rest[..index].to_owned()), &rest[index + 1..]),
+        None => (None, rest),
+    };
+    let index = host_port.rfind(':').ok_or(Error::InvalidInput)?;
+    let host = host_port[..index].to_owned();
+    let port = host_port[index + 1..]
+        .parse::<u16>()
+        .map_err(|_| Error::InvalidInput)?;
+
+    check_host(&interface, &host)?;
+
+    Ok((interface, host, port))
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    /// Parses and validates an address.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The address has no `://` separator, or its transport specific part is malformed.
+    /// - `ProtocolNotSupported` : The transport is not one of `inproc`, `ipc`, `tcp` or `ws`.
+    /// - `NameTooLong` : The address is longer than `NN_SOCKADDR_MAX - 1` bytes.
+    fn from_str(s: &str) -> Result<Address> {
+        let index = s.find("://").ok_or(Error::InvalidInput)?;
+        let scheme = &s[..index];
+        let rest = &s[index + 3..];
+
+        let addr = match scheme {
+            INPROC_SCHEME => Address::Inproc(rest.to_owned()),
+            IPC_SCHEME => Address::Ipc(rest.to_owned()),
+            TCP_SCHEME => {
+                let (interface, host, port) = parse_host_port(rest)?;
+
+                Address::Tcp {
+                    interface,
+                    host,
+                    port,
+                }
+            }
+            WS_SCHEME => {
+                let (authority, path) = match rest.find('/') {
+                    Some(index) => rest.split_at(index),
+                    None => (rest, ""),
+                };
+                let (interface, host, port) = parse_host_port(authority)?;
+
+                Address::Ws {
+                    interface,
+                    host,
+                    port,
+                    path: path.to_owned(),
+                }
+            }
+            _ => return Err(Error::ProtocolNotSupported),
+        };
+
+        addr.validate()?;
+        Ok(addr)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Address::Inproc(ref name) => write!(formatter, "{}://{}", INPROC_SCHEME, name),
+            Address::Ipc(ref path) => write!(formatter, "{}://{}", IPC_SCHEME, path),
+            Address::Tcp {
+                ref interface,
+                ref host,
+                port,
+            } => {
+                write!(formatter, "{}://", TCP_SCHEME)?;
+                if let Some(ref interface) = *interface {
+                    write!(formatter, "{};", interface)?;
+                }
+                write!(formatter, "{}:{}", host, port)
+            }
+            Address::Ws {
+                ref interface,
+                ref host,
+                port,
+                ref path,
+            } => {
+                write!(formatter, "{}://", WS_SCHEME)?;
+                if let Some(ref interface) = *interface {
+                    write!(formatter, "{};", interface)?;
+                }
+                write!(formatter, "{}:{}{}", host, port, path)
+            }
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Address {
+    type Error = Error;
+
+    fn try_from(s: &'a str) -> Result<Address> {
+        s.parse()
+    }
+}
+
+// Keeps `socket.bind(&"tcp://...")`, accepted when addresses were plain `&str`, compiling.
+impl<'a, 'b> TryFrom<&'b &'a str> for Address {
+    type Error = Error;
+
+    fn try_from(s: &'b &'a str) -> Result<Address> {
+        s.parse()
+    }
+}
+
+impl<'a> TryFrom<&'a String> for Address {
+    type Error = Error;
+
+    fn try_from(s: &'a String) -> Result<Address> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for Address {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Address> {
+        s.parse()
+    }
+}
+
+impl<'a> From<&'a Address> for Address {
+    fn from(addr: &'a Address) -> Address {
+        addr.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Address;
+    use crate::Error;
+
+    use std::convert::TryFrom;
+
+    #[test]
+    fn addresses_can_be_parsed() {
+        assert_eq!(
+            Ok(Address::Inproc("foo".to_owned())),
+            "inproc://foo".parse()
+        );
+        assert_eq!(
+            Ok(Address::Ipc("/tmp/foo.ipc".to_owned())),
+            "ipc:///tmp/foo.ipc".parse()
+        );
+        assert_eq!(
+            Ok(Address::Tcp {
+                interface: None,
+                host: "*".to_owned(),
+                port: 5555,
+            }),
+            "tcp://*:5555".parse()
+        );
+        assert_eq!(
+            Ok(Address::Tcp {
+                interface: Some("eth0".to_owned()),
+                host: "10.0.0.1".to_owned(),
+                port: 5555,
+            }),
+            "tcp://eth0;10.0.0.1:5555".parse()
+        );
+        assert_eq!(
+            Ok(Address::Tcp {
+                interface: None,
+                host: "[::1]".to_owned(),
+                port: 5555,
+            }),
+            "tcp://[::1]:5555".parse()
+        );
+        assert_eq!(
+            Ok(Address::Ws {
+                interface: None,
+                host: "localhost".to_owned(),
+                port: 8080,
+                path: "/chat".to_owned(),
+            }),
+            "ws://localhost:8080/chat".parse()
+        );
+    }
+
+    #[test]
+    fn addresses_round_trip_through_strings() {
+        let addrs = [
+            "inproc://foo",
+            "ipc:///tmp/foo.ipc",
+            "tcp://127.0.0.1:5555",
+            "tcp://eth0;10.0.0.1:5555",
+            "ws://*:8080",
+            "ws://lo;127.0.0.1:8080/a/b",
+        ];
+
+        for addr in addrs.iter() {
+            assert_eq!(*addr, addr.parse::<Address>().unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn addresses_can_be_converted_from_strings() {
+        let expected = Ok(Address::Inproc("foo".to_owned()));
+        let owned = "inproc://foo".to_owned();
+
+        assert_eq!(expected, Address::try_from("inproc://foo"));
+        assert_eq!(expected, Address::try_from(&"inproc://foo"));
+        assert_eq!(expected, Address::try_from(&owned));
+        assert_eq!(expected, Address::try_from(owned));
+    }
+
+    #[test]
+    fn malformed_addresses_are_rejected() {
+        assert_eq!(
+            Err(Error::InvalidInput),
+            "127.0.0.1:5555".parse::<Address>()
+        );
+        assert_eq!(Err(Error::InvalidInput), "inproc://".parse::<Address>());
+        assert_eq!(
+            Err(Error::InvalidInput),
+            "tcp://127.0.0.1".parse::<Address>()
+        );
+        assert_eq!(
+            Err(Error::InvalidInput),
+            "tcp://127.0.0.1:".parse::<Address>()
+        );
+        assert_eq!(
+            Err(Error::InvalidInput),
+            "tcp://127.0.0.1:65536".parse::<Address>()
+        );
+        assert_eq!(Err(Error::InvalidInput), "tcp://:5555".parse::<Address>());
+        assert_eq!(
+            Err(Error::InvalidInput),
+            "tcp://;host:5555".parse::<Address>()
+        );
+        assert_eq!(
+            Err(Error::InvalidInput),
+            "tcp://[::1:5555".parse::<Address>()
+        );
+        assert_eq!(
+            Err(Error::ProtocolNotSupported),
+            "udp://127.0.0.1:5555".parse::<Address>()
+        );
+    }
+
+    #[test]
+    fn too_long_addresses_are_rejected() {
+        let name = "a".repeat(nanomsg_sys::NN_SOCKADDR_MAX as usize);
+
+        assert_eq!(
+            Err(Error::NameTooLong),
+            format!("inproc://{}", name).parse::<Address>()
+        );
+    }
+}
@@ -0,0 +1,445 @@
+//! Asynchronous sockets driven by the tokio runtime, see the `tokio` feature.
+//!
+//! The readiness descriptors of the socket, `NN_RCVFD` and `NN_SNDFD`, are registered in the tokio reactor,
+//! messages are then sent and received with `NN_DONTWAIT` once the reactor reports them ready.
+
+use tokio::io::unix::AsyncFd;
+use tokio::io::{AsyncRead, AsyncWrite, Interest, ReadBuf};
+
+use std::future;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use crate::result::{Error, Result};
+use crate::{Message, Socket};
+
+/// A socket whose `send` and `recv` wait for readiness without blocking the thread.
+///
+/// Both operations are cancellation safe: when the future is dropped before completing,
+/// no message was sent or received, so `recv` can be used in `tokio::select!` without losing messages.
+///
+/// # Example
+///
+/// ```rust
+/// use nanomsg::{AsyncSocket, Message, Protocol, Socket};
+///
+/// # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+/// let mut pull_socket = Socket::new(Protocol::Pull).unwrap();
+/// pull_socket.bind("ipc:///tmp/async_socket_doc.ipc").unwrap();
+/// let mut push_socket = Socket::new(Protocol::Push).unwrap();
+/// push_socket.connect("ipc:///tmp/async_socket_doc.ipc").unwrap();
+///
+/// let pull_socket = AsyncSocket::new(pull_socket).unwrap();
+/// let push_socket = AsyncSocket::new(push_socket).unwrap();
+///
+/// push_socket.send(Message::from_slice(b"foobar").unwrap()).await.unwrap();
+///
+/// let msg = pull_socket.recv().await.unwrap();
+/// assert_eq!(b"foobar", &msg[..]);
+/// # });
+/// ```
+pub struct AsyncSocket {
+    // The descriptors belong to the socket, they are deregistered before it closes them.
+    receive_fd: Option<AsyncFd<RawFd>>,
+    send_fd: Option<AsyncFd<RawFd>>,
+    socket: Socket,
+}
+
+/// Registers a readiness descriptor, `None` when the protocol of the socket does not provide it.
+fn register(fd: Result<RawFd>) -> Result<Option<AsyncFd<RawFd>>> {
+    match fd {
+        // nanomsg signals readiness in both directions by making the descriptor readable.
+        Ok(fd) => Ok(Some(AsyncFd::with_interest(fd, Interest::READABLE)?)),
+        Err(Error::ProtocolNotAvailable) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+impl AsyncSocket {
+    /// Registers the socket, or a typed socket, in the reactor of the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime, or if the runtime has no IO driver enabled.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    pub fn new<S: Into<Socket>>(socket: S) -> Result<AsyncSocket> {
+        let mut socket = socket.into();
+        let receive_fd = register(socket.get_receive_fd())?;
+        let send_fd = register(socket.get_send_fd())?;
+
+        Ok(AsyncSocket {
+            receive_fd,
+            send_fd,
+            socket,
+        })
+    }
+
+    /// Returns the underlying socket.
+    pub fn get_ref(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Returns the underlying socket, to bind, connect or set options.
+    pub fn get_mut(&mut self) -> &mut Socket {
+        &mut self.socket
+    }
+
+    /// Removes the socket from the reactor and returns it.
+    pub fn into_inner(self) -> Socket {
+        self.socket
+    }
+
+    /// Sends the message once the socket is ready, see `Socket::send_msg`.
+    /// The message is dropped if the future is cancelled before it could be sent.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was sent.
+    /// - `Terminating` : The library is terminating.
+    pub async fn send(&self, msg: Message) -> Result<usize> {
+        let mut pending = Some(msg);
+
+        future::poll_fn(|cx| self.poll_send(cx, &mut pending)).await
+    }
+
+    /// Receives a message once one is available, see `Socket::recv_msg`.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
+    /// - `Terminating` : The library is terminating.
+    pub async fn recv(&self) -> Result<Message> {
+        future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Attempts to receive a message, registering the current task for wakeup when none is available.
+    /// This is the polling version of `recv`, with the same errors.
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Result<Message>> {
+        self.poll_with(&self.receive_fd, cx, Socket::nb_recv_msg)
+    }
+
+    /// Attempts to receive a message into the buffer, truncating it as `Socket::nb_read` does.
+    pub(crate) fn poll_read_slice(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        self.poll_with(&self.receive_fd, cx, |socket| socket.nb_read(buf))
+    }
+
+    /// Attempts to send the buffer as one message, see `Socket::nb_write`.
+    pub(crate) fn poll_write_slice(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        self.poll_with(&self.send_fd, cx, |socket| socket.nb_write(buf))
+    }
+
+    /// Attempts to send the pending message, leaving it in place while the socket is not ready.
+    pub(crate) fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        pending: &mut Option<Message>,
+    ) -> Poll<Result<usize>> {
+        self.poll_with(&self.send_fd, cx, |socket| match pending.take() {
+            Some(msg) => socket
+                .send_msg_with_flags(msg, nanomsg_sys::NN_DONTWAIT)
+                .map_err(|(err, unsent)| {
+                    if err == Error::TryAgain {
+                        *pending = Some(unsent);
+                    }
+                    err
+                }),
+            None => Ok(0),
+        })
+    }
+
+    /// Runs the non-blocking operation until it stops failing with `TryAgain`,
+    /// waiting for the readiness descriptor in between.
+    /// Without descriptor, the protocol does not support the operation and it fails right away.
+    fn poll_with<T, F>(
+        &self,
+        fd: &Option<AsyncFd<RawFd>>,
+        cx: &mut Context<'_>,
+        mut operation: F,
+    ) -> Poll<Result<T>>
+    where
+        F: FnMut(&Socket) -> Result<T>,
+    {
+        let fd = match *fd {
+            Some(ref fd) => fd,
+            None => return Poll::Ready(operation(&self.socket)),
+        };
+
+        loop {
+            let mut guard = ready!(fd.poll_read_ready(cx))?;
+
+            match operation(&self.socket) {
+                Err(Error::TryAgain) => guard.clear_ready(),
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+}
+
+/// Receives one message per read, see `Socket::read`.
+/// Any bytes exceeding the remaining capacity of the buffer are truncated, they are not returned by the next read.
+/// A read of zero bytes means that an empty message was received, not that the end of the stream was reached,
+/// so helpers such as `read_to_end` should not be used.
+impl AsyncRead for AsyncSocket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let len = ready!(self.poll_read_slice(cx, buf.initialize_unfilled()))?;
+
+        buf.advance(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Sends each buffer as one message, see `Socket::write`.
+/// Flushing and shutting down have no effect, messages are handed to nanomsg as soon as they are written.
+impl AsyncWrite for AsyncSocket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_slice(cx, buf).map_err(io::Error::from)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The `futures` flavour of the `AsyncRead` implementation, with the same truncation.
+#[cfg(feature = "tokio_stream")]
+impl futures_io::AsyncRead for AsyncSocket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_read_slice(cx, buf).map_err(io::Error::from)
+    }
+}
+
+/// The `futures` flavour of the `AsyncWrite` implementation, each buffer is sent as one message.
+#[cfg(feature = "tokio_stream")]
+impl futures_io::AsyncWrite for AsyncSocket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_slice(cx, buf).map_err(io::Error::from)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncSocket;
+    use crate::{Error, Message, Protocol, Socket};
+
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::time::{sleep, timeout};
+
+    fn connected_pair(url: &str, server: Protocol, client: Protocol) -> (AsyncSocket, AsyncSocket) {
+        let mut server_socket = Socket::new(server).unwrap();
+        let mut client_socket = Socket::new(client).unwrap();
+
+        server_socket.bind(url).unwrap();
+        client_socket.connect(url).unwrap();
+
+        (
+            AsyncSocket::new(server_socket).unwrap(),
+            AsyncSocket::new(client_socket).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn send_and_recv_messages() {
+        let url = "ipc:///tmp/async_send_and_recv_messages.ipc";
+        let (pull_socket, push_socket) = connected_pair(url, Protocol::Pull, Protocol::Push);
+
+        push_socket
+            .send(Message::from_slice(b"foobar").unwrap())
+            .await
+            .unwrap();
+        let msg = pull_socket.recv().await.unwrap();
+
+        assert_eq!(b"foobar", &msg[..]);
+    }
+
+    #[tokio::test]
+    async fn recv_waits_for_a_message() {
+        let url = "ipc:///tmp/async_recv_waits_for_a_message.ipc";
+        let (pull_socket, push_socket) = connected_pair(url, Protocol::Pull, Protocol::Push);
+
+        let sender = tokio::spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            push_socket
+                .send(Message::from_slice(b"late").unwrap())
+                .await
+                .unwrap();
+            push_socket
+        });
+
+        let msg = timeout(Duration::from_secs(1), pull_socket.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(b"late", &msg[..]);
+        drop(sender.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn cancelled_recv_does_not_lose_messages() {
+        let url = "ipc:///tmp/async_cancelled_recv_does_not_lose_messages.ipc";
+        let (pull_socket, push_socket) = connected_pair(url, Protocol::Pull, Protocol::Push);
+
+        assert!(timeout(Duration::from_millis(10), pull_socket.recv())
+            .await
+            .is_err());
+
+        push_socket
+            .send(Message::from_slice(b"first").unwrap())
+            .await
+            .unwrap();
+        push_socket
+            .send(Message::from_slice(b"second").unwrap())
+            .await
+            .unwrap();
+
+        tokio::select! {
+            msg = pull_socket.recv() => assert_eq!(b"first", &msg.unwrap()[..]),
+            _ = sleep(Duration::from_secs(1)) => panic!("no message received"),
+        }
+
+        let msg = pull_socket.recv().await.unwrap();
+        assert_eq!(b"second", &msg[..]);
+    }
+
+    #[tokio::test]
+    async fn request_reply_round_trip() {
+        let url = "ipc:///tmp/async_request_reply_round_trip.ipc";
+        let (rep_socket, req_socket) = connected_pair(url, Protocol::Rep, Protocol::Req);
+
+        let server = tokio::spawn(async move {
+            let request = rep_socket.recv().await.unwrap();
+            let mut reply = Message::from_slice(b"re: ").unwrap();
+
+            reply.extend_from_slice(&request).unwrap();
+            rep_socket.send(reply).await.unwrap();
+        });
+
+        req_socket
+            .send(Message::from_slice(b"ping").unwrap())
+            .await
+            .unwrap();
+        let reply = req_socket.recv().await.unwrap();
+
+        assert_eq!(b"re: ping", &reply[..]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn errors_are_nanomsg_errors() {
+        let push_socket = AsyncSocket::new(Socket::new(Protocol::Push).unwrap()).unwrap();
+        let pull_socket = AsyncSocket::new(Socket::new(Protocol::Pull).unwrap()).unwrap();
+
+        assert_eq!(
+            Error::OperationNotSupported,
+            push_socket.recv().await.unwrap_err()
+        );
+        assert_eq!(
+            Error::OperationNotSupported,
+            pull_socket
+                .send(Message::from_slice(b"foo").unwrap())
+                .await
+                .unwrap_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn each_write_is_one_message() {
+        let url = "ipc:///tmp/async_each_write_is_one_message.ipc";
+        let (mut pull_socket, mut push_socket) =
+            connected_pair(url, Protocol::Pull, Protocol::Push);
+        let mut buf = [0u8; 16];
+
+        assert_eq!(3, push_socket.write(b"foo").await.unwrap());
+        push_socket.write_all(b"barbaz").await.unwrap();
+
+        assert_eq!(3, pull_socket.read(&mut buf).await.unwrap());
+        assert_eq!(b"foo", &buf[..3]);
+        assert_eq!(6, pull_socket.read(&mut buf).await.unwrap());
+        assert_eq!(b"barbaz", &buf[..6]);
+    }
+
+    #[tokio::test]
+    async fn reads_truncate_messages() {
+        let url = "ipc:///tmp/async_reads_truncate_messages.ipc";
+        let (mut pull_socket, mut push_socket) =
+            connected_pair(url, Protocol::Pull, Protocol::Push);
+        let mut buf = [0u8; 3];
+
+        push_socket.write_all(b"foobar").await.unwrap();
+        push_socket.write_all(b"baz").await.unwrap();
+
+        assert_eq!(3, pull_socket.read(&mut buf).await.unwrap());
+        assert_eq!(b"foo", &buf);
+        // The end of the first message was discarded.
+        assert_eq!(3, pull_socket.read(&mut buf).await.unwrap());
+        assert_eq!(b"baz", &buf);
+    }
+
+    #[cfg(feature = "tokio_stream")]
+    #[tokio::test]
+    async fn futures_io_exchanges_messages() {
+        use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+        let url = "ipc:///tmp/async_futures_io_exchanges_messages.ipc";
+        let (mut pull_socket, mut push_socket) =
+            connected_pair(url, Protocol::Pull, Protocol::Push);
+        let mut buf = [0u8; 4];
+
+        AsyncWriteExt::write_all(&mut push_socket, b"foobar")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            4,
+            AsyncReadExt::read(&mut pull_socket, &mut buf)
+                .await
+                .unwrap()
+        );
+        assert_eq!(b"foob", &buf);
+    }
+}
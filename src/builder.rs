@@ -0,0 +1,499 @@
+use std::error;
+use std::fmt;
+use std::time::Duration;
+
+use crate::{Endpoint, Error, Protocol, Socket, WsMessageType};
+
+/// The step of `SocketBuilder::build` that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildStep {
+    /// Creating the socket itself.
+    Create,
+    /// Validating or setting the named option.
+    Option(&'static str),
+    /// Validating or applying a subscription to the topic.
+    Subscribe(Vec<u8>),
+    /// Binding the socket to the address.
+    Bind(String),
+    /// Connecting the socket to the address.
+    Connect(String),
+}
+
+impl fmt::Display for BuildStep {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildStep::Create => write!(formatter, "create socket"),
+            BuildStep::Option(name) => write!(formatter, "set option '{}'", name),
+            BuildStep::Subscribe(ref topic) => write!(
+                formatter,
+                "subscribe to '{}'",
+                String::from_utf8_lossy(topic)
+            ),
+            BuildStep::Bind(ref addr) => write!(formatter, "bind to '{}'", addr),
+            BuildStep::Connect(ref addr) => write!(formatter, "connect to '{}'", addr),
+        }
+    }
+}
+
+/// The error returned by `SocketBuilder::build`, naming the step that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildError {
+    step: BuildStep,
+    error: Error,
+}
+
+impl BuildError {
+    fn new(step: BuildStep, error: Error) -> BuildError {
+        BuildError { step, error }
+    }
+
+    /// Returns the step that failed.
+    pub fn step(&self) -> &BuildStep {
+        &self.step
+    }
+
+    /// Returns the underlying nanomsg error.
+    pub fn error(&self) -> Error {
+        self.error
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "failed to {}: {}", self.step, self.error)
+    }
+}
+
+impl error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(err: BuildError) -> Error {
+        err.error
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SocketOption {
+    Linger(Option<Duration>),
+    SendBufferSize(usize),
+    ReceiveBufferSize(usize),
+    ReceiveMaxSize(isize),
+    SendTimeout(Option<Duration>),
+    ReceiveTimeout(Option<Duration>),
+    ReconnectInterval(Duration),
+    MaxReconnectInterval(Duration),
+    SendPriority(u8),
+    ReceivePriority(u8),
+    Ipv4Only(bool),
+    MaxTtl(u8),
+    SocketName(String),
+    TcpNoDelay(bool),
+    WsMessageType(WsMessageType),
+    SurveyDeadline(Duration),
+    RequestResendInterval(Duration),
+}
+
+impl SocketOption {
+    fn name(&self) -> &'static str {
+        match *self {
+            SocketOption::Linger(_) => "linger",
+            SocketOption::SendBufferSize(_) => "send_buffer_size",
+            SocketOption::ReceiveBufferSize(_) => "receive_buffer_size",
+            SocketOption::ReceiveMaxSize(_) => "receive_max_size",
+            SocketOption::SendTimeout(_) => "send_timeout",
+            SocketOption::ReceiveTimeout(_) => "receive_timeout",
+            SocketOption::ReconnectInterval(_) => "reconnect_interval",
+            SocketOption::MaxReconnectInterval(_) => "max_reconnect_interval",
+            SocketOption::SendPriority(_) => "send_priority",
+            SocketOption::ReceivePriority(_) => "receive_priority",
+            SocketOption::Ipv4Only(_) => "ipv4_only",
+            SocketOption::MaxTtl(_) => "max_ttl",
+            SocketOption::SocketName(_) => "socket_name",
+            SocketOption::TcpNoDelay(_) => "tcp_nodelay",
+            SocketOption::WsMessageType(_) => "ws_msg_type",
+            SocketOption::SurveyDeadline(_) => "survey_deadline",
+            SocketOption::RequestResendInterval(_) => "request_resend_interval",
+        }
+    }
+
+    fn validate(&self, protocol: Protocol) -> Result<(), Error> {
+        match *self {
+            SocketOption::SendPriority(priority) | SocketOption::ReceivePriority(priority)
+                if !(1..=16).contains(&priority) =>
+            {
+                Err(Error::InvalidInput)
+            }
+            SocketOption::MaxTtl(0) => Err(Error::InvalidInput),
+            #[cfg(windows)]
+            SocketOption::SocketName(_) => Err(Error::OperationNotSupported),
+            SocketOption::SurveyDeadline(_) if protocol != Protocol::Surveyor => {
+                Err(Error::ProtocolNotAvailable)
+            }
+            SocketOption::RequestResendInterval(_) if protocol != Protocol::Req => {
+                Err(Error::ProtocolNotAvailable)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(&self, socket: &mut Socket) -> Result<(), Error> {
+        match *self {
+            SocketOption::Linger(linger) => socket.set_linger_duration(linger),
+            SocketOption::SendBufferSize(size) => socket.set_send_buffer_size(size),
+            SocketOption::ReceiveBufferSize(size) => socket.set_receive_buffer_size(size),
+            SocketOption::ReceiveMaxSize(size) => socket.set_receive_max_size(size),
+            SocketOption::SendTimeout(timeout) => socket.set_send_timeout_duration(timeout),
+            SocketOption::ReceiveTimeout(timeout) => socket.set_receive_timeout_duration(timeout),
+            SocketOption::ReconnectInterval(interval) => {
+                socket.set_reconnect_interval_duration(interval)
+            }
+            SocketOption::MaxReconnectInterval(interval) => {
+                socket.set_max_reconnect_interval_duration(interval)
+            }
+            SocketOption::SendPriority(priority) => socket.set_send_priority(priority),
+            SocketOption::ReceivePriority(priority) => socket.set_receive_priority(priority),
+            SocketOption::Ipv4Only(ipv4_only) => socket.set_ipv4_only(ipv4_only),
+            SocketOption::MaxTtl(ttl) => socket.set_max_ttl(ttl),
+            #[cfg(not(windows))]
+            SocketOption::SocketName(ref name) => socket.set_socket_name(name),
+            #[cfg(windows)]
+            SocketOption::SocketName(_) => Err(Error::OperationNotSupported),
+            SocketOption::TcpNoDelay(tcp_nodelay) => socket.set_tcp_nodelay(tcp_nodelay),
+            SocketOption::WsMessageType(msg_type) => socket.set_ws_msg_type(msg_type),
+            SocketOption::SurveyDeadline(deadline) => socket.set_survey_deadline_duration(deadline),
+            SocketOption::RequestResendInterval(interval) => {
+                socket.set_request_resend_interval_duration(interval)
+            }
+        }
+    }
+}
+
+/// Collects the protocol, options, subscriptions and endpoints of a socket,
+/// so that it can be created and configured in a single fallible call.
+///
+/// Protocol specific settings are validated before the socket is created.
+/// Options are then applied before subscriptions, and all of them before the endpoints are added,
+/// so that options such as priorities apply to every endpoint. Binds are done before connects.
+/// If any step fails, the partially configured socket is closed and the failing step is reported.
+///
+/// # Example
+///
+/// ```rust
+/// use nanomsg::{Protocol, SocketBuilder};
+/// use std::time::Duration;
+///
+/// let (socket, endpoints) = SocketBuilder::new(Protocol::Sub)
+///     .receive_timeout(Some(Duration::from_secs(1)))
+///     .subscribe(b"news")
+///     .connect("ipc:///tmp/socket_builder_doc.ipc")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SocketBuilder {
+    protocol: Protocol,
+    raw: bool,
+    options: Vec<SocketOption>,
+    subscriptions: Vec<Vec<u8>>,
+    binds: Vec<String>,
+    connects: Vec<String>,
+}
+
+impl SocketBuilder {
+    /// Starts the configuration of a socket of the specified protocol.
+    pub fn new(protocol: Protocol) -> SocketBuilder {
+        SocketBuilder {
+            protocol,
+            raw: false,
+            options: Vec::new(),
+            subscriptions: Vec::new(),
+            binds: Vec::new(),
+            connects: Vec::new(),
+        }
+    }
+
+    /// Creates the socket with `Socket::new_for_device` instead of `Socket::new`.
+    pub fn for_device(mut self) -> SocketBuilder {
+        self.raw = true;
+        self
+    }
+
+    /// See `Socket::set_linger_duration`.
+    pub fn linger(self, linger: Option<Duration>) -> SocketBuilder {
+        self.option(SocketOption::Linger(linger))
+    }
+
+    /// See `Socket::set_send_buffer_size`.
+    pub fn send_buffer_size(self, size_in_bytes: usize) -> SocketBuilder {
+        self.option(SocketOption::SendBufferSize(size_in_bytes))
+    }
+
+    /// See `Socket::set_receive_buffer_size`.
+    pub fn receive_buffer_size(self, size_in_bytes: usize) -> SocketBuilder {
+        self.option(SocketOption::ReceiveBufferSize(size_in_bytes))
+    }
+
+    /// See `Socket::set_receive_max_size`.
+    pub fn receive_max_size(self, size_in_bytes: isize) -> SocketBuilder {
+        self.option(SocketOption::ReceiveMaxSize(size_in_bytes))
+    }
+
+    /// See `Socket::set_send_timeout_duration`.
+    pub fn send_timeout(self, timeout: Option<Duration>) -> SocketBuilder {
+        self.option(SocketOption::SendTimeout(timeout))
+    }
+
+    /// See `Socket::set_receive_timeout_duration`.
+    pub fn receive_timeout(self, timeout: Option<Duration>) -> SocketBuilder {
+        self.option(SocketOption::ReceiveTimeout(timeout))
+    }
+
+    /// See `Socket::set_reconnect_interval_duration`.
+    pub fn reconnect_interval(self, interval: Duration) -> SocketBuilder {
+        self.option(SocketOption::ReconnectInterval(interval))
+    }
+
+    /// See `Socket::set_max_reconnect_interval_duration`.
+    pub fn max_reconnect_interval(self, interval: Duration) -> SocketBuilder {
+        self.option(SocketOption::MaxReconnectInterval(interval))
+    }
+
+    /// See `Socket::set_send_priority`, the priority must be between 1 and 16.
+    pub fn send_priority(self, priority: u8) -> SocketBuilder {
+        self.option(SocketOption::SendPriority(priority))
+    }
+
+    /// See `Socket::set_receive_priority`, the priority must be between 1 and 16.
+    pub fn receive_priority(self, priority: u8) -> SocketBuilder {
+        self.option(SocketOption::ReceivePriority(priority))
+    }
+
+    /// See `Socket::set_ipv4_only`.
+    pub fn ipv4_only(self, ipv4_only: bool) -> SocketBuilder {
+        self.option(SocketOption::Ipv4Only(ipv4_only))
+    }
+
+    /// See `Socket::set_max_ttl`, the value must be between 1 and 255.
+    pub fn max_ttl(self, ttl: u8) -> SocketBuilder {
+        self.option(SocketOption::MaxTtl(ttl))
+    }
+
+    /// See `Socket::set_socket_name`.
+    /// It is not available on Windows, where `build` fails with `OperationNotSupported`.
+    pub fn socket_name(self, name: &str) -> SocketBuilder {
+        self.option(SocketOption::SocketName(name.to_owned()))
+    }
+
+    /// See `Socket::set_tcp_nodelay`.
+    pub fn tcp_nodelay(self, tcp_nodelay: bool) -> SocketBuilder {
+        self.option(SocketOption::TcpNoDelay(tcp_nodelay))
+    }
+
+    /// See `Socket::set_ws_msg_type`.
+    pub fn ws_msg_type(self, msg_type: WsMessageType) -> SocketBuilder {
+        self.option(SocketOption::WsMessageType(msg_type))
+    }
+
+    /// See `Socket::set_survey_deadline_duration`, only valid on `Surveyor` sockets.
+    pub fn survey_deadline(self, deadline: Duration) -> SocketBuilder {
+        self.option(SocketOption::SurveyDeadline(deadline))
+    }
+
+    /// See `Socket::set_request_resend_interval_duration`, only valid on `Req` sockets.
+    pub fn request_resend_interval(self, interval: Duration) -> SocketBuilder {
+        self.option(SocketOption::RequestResendInterval(interval))
+    }
+
+    /// See `Socket::subscribe`, only valid on `Sub` sockets.
+    pub fn subscribe(mut self, topic: &[u8]) -> SocketBuilder {
+        self.subscriptions.push(topic.to_vec());
+        self
+    }
+
+    /// Adds a local endpoint, see `Socket::bind`.
+    pub fn bind(mut self, addr: &str) -> SocketBuilder {
+        self.binds.push(addr.to_owned());
+        self
+    }
+
+    /// Adds a remote endpoint, see `Socket::connect`.
+    pub fn connect(mut self, addr: &str) -> SocketBuilder {
+        self.connects.push(addr.to_owned());
+        self
+    }
+
+    fn option(mut self, option: SocketOption) -> SocketBuilder {
+        self.options.push(option);
+        self
+    }
+
+    fn validate(&self) -> Result<(), BuildError> {
+        for option in &self.options {
+            option
+                .validate(self.protocol)
+                .map_err(|err| BuildError::new(BuildStep::Option(option.name()), err))?;
+        }
+
+        if self.protocol != Protocol::Sub {
+            if let Some(topic) = self.subscriptions.first() {
+                let step = BuildStep::Subscribe(topic.clone());
+                return Err(BuildError::new(step, Error::ProtocolNotAvailable));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the socket, applies the options and subscriptions, then adds the endpoints.
+    /// Returns the socket with its endpoints, binds first, in the order they were specified.
+    ///
+    /// # Error
+    ///
+    /// The returned `BuildError` names the failing step and holds the nanomsg error, which is:
+    ///
+    /// - `ProtocolNotAvailable` : An option or a subscription is not valid for the protocol.
+    /// - `InvalidInput` : An option value is out of range.
+    /// - Any error returned by `Socket::new`, the option setters, `Socket::bind` or `Socket::connect`.
+    pub fn build(&self) -> Result<(Socket, Vec<Endpoint>), BuildError> {
+        self.validate()?;
+
+        let mut socket = if self.raw {
+            Socket::new_for_device(self.protocol)
+        } else {
+            Socket::new(self.protocol)
+        }
+        .map_err(|err| BuildError::new(BuildStep::Create, err))?;
+
+        for option in &self.options {
+            option
+                .apply(&mut socket)
+                .map_err(|err| BuildError::new(BuildStep::Option(option.name()), err))?;
+        }
+
+        for topic in &self.subscriptions {
+            socket
+                .subscribe(topic)
+                .map_err(|err| BuildError::new(BuildStep::Subscribe(topic.clone()), err))?;
+        }
+
+        let mut endpoints = Vec::with_capacity(self.binds.len() + self.connects.len());
+
+        for addr in &self.binds {
+            let endpoint = socket
+                .bind(addr)
+                .map_err(|err| BuildError::new(BuildStep::Bind(addr.clone()), err))?;
+            endpoints.push(endpoint);
+        }
+
+        for addr in &self.connects {
+            let endpoint = socket
+                .connect(addr)
+                .map_err(|err| BuildError::new(BuildStep::Connect(addr.clone()), err))?;
+            endpoints.push(endpoint);
+        }
+
+        Ok((socket, endpoints))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuildError, BuildStep, SocketBuilder};
+    use crate::{Error, Protocol};
+
+    use std::io::{Read, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    fn build_err(builder: SocketBuilder) -> BuildError {
+        match builder.build() {
+            Ok(_) => panic!("build should have failed"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn builder_creates_connected_sockets() {
+        let url = "ipc:///tmp/builder_creates_connected_sockets.ipc";
+
+        let (mut push_socket, push_endpoints) = SocketBuilder::new(Protocol::Push)
+            .linger(None)
+            .send_timeout(Some(Duration::from_millis(500)))
+            .bind(url)
+            .build()
+            .unwrap();
+        let (mut pull_socket, pull_endpoints) = SocketBuilder::new(Protocol::Pull)
+            .receive_timeout(Some(Duration::from_millis(500)))
+            .receive_priority(2)
+            .connect(url)
+            .build()
+            .unwrap();
+
+        assert_eq!(1, push_endpoints.len());
+        assert_eq!(1, pull_endpoints.len());
+        assert_eq!(None, push_socket.get_linger_duration().unwrap());
+        assert_eq!(2, pull_socket.get_receive_priority().unwrap());
+
+        thread::sleep(Duration::from_millis(10));
+
+        push_socket.write_all(b"foobar").unwrap();
+        let mut text = String::new();
+        pull_socket.read_to_string(&mut text).unwrap();
+        assert_eq!("foobar", text);
+    }
+
+    #[test]
+    fn builder_rejects_subscription_on_non_sub_protocol() {
+        let err = build_err(SocketBuilder::new(Protocol::Pull).subscribe(b"foo"));
+
+        assert_eq!(&BuildStep::Subscribe(b"foo".to_vec()), err.step());
+        assert_eq!(Error::ProtocolNotAvailable, err.error());
+    }
+
+    #[test]
+    fn builder_rejects_protocol_specific_options() {
+        let err = build_err(
+            SocketBuilder::new(Protocol::Respondent).survey_deadline(Duration::from_secs(1)),
+        );
+
+        assert_eq!(&BuildStep::Option("survey_deadline"), err.step());
+        assert_eq!(Error::ProtocolNotAvailable, err.error());
+
+        let err = build_err(
+            SocketBuilder::new(Protocol::Rep).request_resend_interval(Duration::from_secs(1)),
+        );
+
+        assert_eq!(&BuildStep::Option("request_resend_interval"), err.step());
+    }
+
+    #[test]
+    fn builder_rejects_out_of_range_priority() {
+        let err = build_err(SocketBuilder::new(Protocol::Push).send_priority(17));
+
+        assert_eq!(&BuildStep::Option("send_priority"), err.step());
+        assert_eq!(Error::InvalidInput, err.error());
+    }
+
+    #[test]
+    fn builder_reports_failing_endpoint() {
+        let err = build_err(
+            SocketBuilder::new(Protocol::Pair)
+                .bind("ipc:///tmp/builder_reports_failing_endpoint.ipc")
+                .connect("not an address"),
+        );
+
+        assert_eq!(&BuildStep::Connect("not an address".to_owned()), err.step());
+        assert_eq!(Error::InvalidInput, err.error());
+        assert_eq!(
+            format!(
+                "failed to connect to 'not an address': {}",
+                Error::InvalidInput
+            ),
+            err.to_string()
+        );
+    }
+}
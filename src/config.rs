@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+use crate::builder::{BuildError, SocketBuilder};
+use crate::{Endpoint, Protocol, Socket, WsMessageType};
+
+/// The description of a socket, its options, subscriptions and endpoints,
+/// that can be loaded from any format supported by serde.
+///
+/// Durations are expressed in milliseconds. As with `Socket::set_linger` and the timeout setters,
+/// a negative `linger`, `send_timeout` or `receive_timeout` means infinite.
+/// Unset options keep the nanomsg default values.
+///
+/// # Example
+///
+/// ```toml
+/// protocol = "sub"
+/// connect = ["tcp://127.0.0.1:5555"]
+/// subscribe = ["news"]
+/// receive_timeout = 1000
+/// ```
+///
+/// **See also:** `SocketBuilder`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SocketConfig {
+    pub protocol: Protocol,
+    /// Creates the socket with `Socket::new_for_device`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub device: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bind: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connect: Vec<String>,
+    /// Topics subscribed to, only valid on `Sub` sockets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subscribe: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linger: Option<isize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_buffer_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receive_buffer_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receive_max_size: Option<isize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_timeout: Option<isize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receive_timeout: Option<isize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconnect_interval: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_reconnect_interval: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_priority: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receive_priority: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipv4_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_ttl: Option<u8>,
+    /// Not available on Windows, where `build` fails with `OperationNotSupported`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_nodelay: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ws_msg_type: Option<WsMessageType>,
+    /// Only valid on `Surveyor` sockets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub survey_deadline: Option<u64>,
+    /// Only valid on `Req` sockets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_resend_interval: Option<u64>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn infinite_or_millis(millis: isize) -> Option<Duration> {
+    if millis < 0 {
+        None
+    } else {
+        Some(Duration::from_millis(millis as u64))
+    }
+}
+
+impl SocketConfig {
+    /// Creates a configuration for the specified protocol, with no option nor endpoint.
+    pub fn new(protocol: Protocol) -> SocketConfig {
+        SocketConfig {
+            protocol,
+            device: false,
+            bind: Vec::new(),
+            connect: Vec::new(),
+            subscribe: Vec::new(),
+            linger: None,
+            send_buffer_size: None,
+            receive_buffer_size: None,
+            receive_max_size: None,
+            send_timeout: None,
+            receive_timeout: None,
+            reconnect_interval: None,
+            max_reconnect_interval: None,
+            send_priority: None,
+            receive_priority: None,
+            ipv4_only: None,
+            max_ttl: None,
+            socket_name: None,
+            tcp_nodelay: None,
+            ws_msg_type: None,
+            survey_deadline: None,
+            request_resend_interval: None,
+        }
+    }
+
+    /// Converts the configuration into a builder, so it can be further customized.
+    pub fn builder(&self) -> SocketBuilder {
+        let mut builder = SocketBuilder::new(self.protocol);
+
+        if self.device {
+            builder = builder.for_device();
+        }
+        if let Some(linger) = self.linger {
+            builder = builder.linger(infinite_or_millis(linger));
+        }
+        if let Some(size) = self.send_buffer_size {
+            builder = builder.send_buffer_size(size);
+        }
+        if let Some(size) = self.receive_buffer_size {
+            builder = builder.receive_buffer_size(size);
+        }
+        if let Some(size) = self.receive_max_size {
+            builder = builder.receive_max_size(size);
+        }
+        if let Some(timeout) = self.send_timeout {
+            builder = builder.send_timeout(infinite_or_millis(timeout));
+        }
+        if let Some(timeout) = self.receive_timeout {
+            builder = builder.receive_timeout(infinite_or_millis(timeout));
+        }
+        if let Some(interval) = self.reconnect_interval {
+            builder = builder.reconnect_interval(Duration::from_millis(interval));
+        }
+        if let Some(interval) = self.max_reconnect_interval {
+            builder = builder.max_reconnect_interval(Duration::from_millis(interval));
+        }
+        if let Some(priority) = self.send_priority {
+            builder = builder.send_priority(priority);
+        }
+        if let Some(priority) = self.receive_priority {
+            builder = builder.receive_priority(priority);
+        }
+        if let Some(ipv4_only) = self.ipv4_only {
+            builder = builder.ipv4_only(ipv4_only);
+        }
+        if let Some(ttl) = self.max_ttl {
+            builder = builder.max_ttl(ttl);
+        }
+        if let Some(ref name) = self.socket_name {
+            builder = builder.socket_name(name);
+        }
+        if let Some(tcp_nodelay) = self.tcp_nodelay {
+            builder = builder.tcp_nodelay(tcp_nodelay);
+        }
+        if let Some(msg_type) = self.ws_msg_type {
+            builder = builder.ws_msg_type(msg_type);
+        }
+        if let Some(deadline) = self.survey_deadline {
+            builder = builder.survey_deadline(Duration::from_millis(deadline));
+        }
+        if let Some(interval) = self.request_resend_interval {
+            builder = builder.request_resend_interval(Duration::from_millis(interval));
+        }
+        for topic in &self.subscribe {
+            builder = builder.subscribe(topic.as_bytes());
+        }
+        for addr in &self.bind {
+            builder = builder.bind(addr);
+        }
+        for addr in &self.connect {
+            builder = builder.connect(addr);
+        }
+
+        builder
+    }
+
+    /// Creates the socket described by the configuration, see `SocketBuilder::build`.
+    pub fn build(&self) -> Result<(Socket, Vec<Endpoint>), BuildError> {
+        self.builder().build()
+    }
+}
+
+impl<'a> From<&'a SocketConfig> for SocketBuilder {
+    fn from(config: &'a SocketConfig) -> SocketBuilder {
+        config.builder()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SocketConfig;
+    use crate::{BuildStep, Error, Protocol, Transport};
+
+    use std::io::{Read, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn config_can_be_deserialized() {
+        let config: SocketConfig = toml::from_str(
+            r#"
+            protocol = "sub"
+            connect = ["ipc:///tmp/config_can_be_deserialized.ipc"]
+            subscribe = ["foo", "bar"]
+            linger = -1
+            receive_timeout = 500
+            receive_priority = 3
+            "#,
+        )
+        .unwrap();
+
+        let mut expected = SocketConfig::new(Protocol::Sub);
+        expected.connect = vec!["ipc:///tmp/config_can_be_deserialized.ipc".to_owned()];
+        expected.subscribe = vec!["foo".to_owned(), "bar".to_owned()];
+        expected.linger = Some(-1);
+        expected.receive_timeout = Some(500);
+        expected.receive_priority = Some(3);
+
+        assert_eq!(expected, config);
+    }
+
+    #[test]
+    fn config_round_trips_through_toml() {
+        let mut config = SocketConfig::new(Protocol::Surveyor);
+        config.bind = vec!["tcp://127.0.0.1:5490".to_owned()];
+        config.survey_deadline = Some(1000);
+
+        let text = toml::to_string(&config).unwrap();
+
+        assert_eq!(config, toml::from_str(&text).unwrap());
+    }
+
+    #[test]
+    fn config_rejects_unknown_fields() {
+        let result: Result<SocketConfig, _> = toml::from_str(
+            r#"
+            protocol = "push"
+            send_timout = 500
+            "#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn protocol_and_transport_are_serialized_in_lowercase() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Doc {
+            protocol: Protocol,
+            transport: Transport,
+        }
+
+        let doc = Doc {
+            protocol: Protocol::Respondent,
+            transport: Transport::Ipc,
+        };
+        let text = toml::to_string(&doc).unwrap();
+
+        assert_eq!("protocol = \"respondent\"\ntransport = \"ipc\"\n", text);
+        assert_eq!(doc, toml::from_str(&text).unwrap());
+    }
+
+    #[test]
+    fn config_builds_connected_sockets() {
+        let url = "ipc:///tmp/config_builds_connected_sockets.ipc";
+        let mut push_config = SocketConfig::new(Protocol::Push);
+        let mut pull_config = SocketConfig::new(Protocol::Pull);
+
+        push_config.bind = vec![url.to_owned()];
+        push_config.send_timeout = Some(500);
+        pull_config.connect = vec![url.to_owned()];
+        pull_config.receive_timeout = Some(500);
+
+        let (mut push_socket, _) = push_config.build().unwrap();
+        let (mut pull_socket, endpoints) = pull_config.build().unwrap();
+
+        assert_eq!(1, endpoints.len());
+        assert_eq!(
+            Some(Duration::from_millis(500)),
+            pull_socket.get_receive_timeout_duration().unwrap()
+        );
+
+        thread::sleep(Duration::from_millis(10));
+
+        push_socket.write_all(b"foobar").unwrap();
+        let mut text = String::new();
+        pull_socket.read_to_string(&mut text).unwrap();
+        assert_eq!("foobar", text);
+    }
+
+    #[test]
+    fn config_reports_invalid_subscription() {
+        let mut config = SocketConfig::new(Protocol::Pub);
+        config.subscribe = vec!["foo".to_owned()];
+
+        match config.build() {
+            Ok(_) => panic!("build should have failed"),
+            Err(err) => {
+                assert_eq!(&BuildStep::Subscribe(b"foo".to_vec()), err.step());
+                assert_eq!(Error::ProtocolNotAvailable, err.error());
+            }
+        }
+    }
+}
@@ -1,25 +1,124 @@
 use libc::c_int;
 
-use crate::result::{last_nano_error, Result};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+use crate::address::Address;
+use crate::result::{last_nano_error, Error, Result};
+
+/// Tells whether an endpoint was created by `Socket::bind` or `Socket::connect`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum EndpointKind {
+    /// Local endpoint, accepting connections.
+    Bind,
+    /// Remote endpoint, the socket connects to it.
+    Connect,
+}
+
+impl fmt::Display for EndpointKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EndpointKind::Bind => write!(formatter, "bind"),
+            EndpointKind::Connect => write!(formatter, "connect"),
+        }
+    }
+}
 
 /// An endpoint created for a specific socket. Each endpoint is identified
 /// by a unique return value that can be further passed to a shutdown
 /// function. The shutdown is done through the endpoint itself and not the Socket
+///
+/// Endpoints created by `Socket::bind` and `Socket::connect` are also tracked by their socket,
+/// see `Socket::endpoints`. A clone refers to the same nanomsg endpoint.
+#[derive(Debug, Clone)]
 pub struct Endpoint {
     value: c_int,
     socket: c_int,
+    addr: Address,
+    kind: EndpointKind,
+    // `None` for endpoints built with `Endpoint::new`, which are not tracked by their socket.
+    registry: Option<Weak<EndpointRegistry>>,
 }
 
 impl Endpoint {
-    pub fn new(value: c_int, socket: c_int) -> Endpoint {
-        Endpoint { value, socket }
+    /// Creates an endpoint from the id returned by `nn_bind` or `nn_connect` on the socket.
+    /// Unlike the endpoints returned by `Socket::bind` and `Socket::connect`,
+    /// it is not tracked by the socket, so shutting it down after the socket is closed is not detected.
+    ///
+    /// The constructor used to take the two ids only, the address and kind are now required too.
+    pub fn new(value: c_int, socket: c_int, addr: Address, kind: EndpointKind) -> Endpoint {
+        Endpoint {
+            value,
+            socket,
+            addr,
+            kind,
+            registry: None,
+        }
+    }
+
+    /// Adds the endpoint to the registry of its socket, so it is removed from it on shutdown.
+    pub(crate) fn register(mut self, registry: &Arc<EndpointRegistry>) -> Endpoint {
+        self.registry = Some(Arc::downgrade(registry));
+        registry.lock().push(self.clone());
+        self
+    }
+
+    /// Returns the id of the endpoint, unique within its socket.
+    pub fn id(&self) -> c_int {
+        self.value
+    }
+
+    /// Returns the id of the socket that created the endpoint.
+    pub fn socket_id(&self) -> c_int {
+        self.socket
+    }
+
+    /// Returns the address the endpoint was created with.
+    pub fn address(&self) -> &Address {
+        &self.addr
+    }
+
+    /// Tells whether the endpoint was created by `bind` or `connect`.
+    pub fn kind(&self) -> EndpointKind {
+        self.kind
     }
 
     /// Removes an endpoint from the socket that created it (via `bind` or `connect`).
     /// The call will return immediately, however,
     /// the library will try to deliver any outstanding outbound messages to the endpoint
     /// for the time specified by `Socket::set_linger`.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket was closed, its id may already belong to another socket.
+    /// - `InvalidInput` : The endpoint was already shut down.
     pub fn shutdown(&mut self) -> Result<()> {
+        let registry = match self.registry {
+            Some(ref registry) => registry.upgrade().ok_or(Error::BadFileDescriptor)?,
+            None => return self.shutdown_untracked(),
+        };
+        let mut endpoints = registry.lock_open().ok_or(Error::BadFileDescriptor)?;
+
+        if !endpoints
+            .iter()
+            .any(|endpoint| endpoint.value == self.value)
+        {
+            return Err(Error::InvalidInput);
+        }
+
+        let ret = unsafe { nanomsg_sys::nn_shutdown(self.socket, self.value) };
+
+        if ret == -1 {
+            return Err(last_nano_error());
+        }
+
+        endpoints.retain(|endpoint| endpoint.value != self.value);
+        Ok(())
+    }
+
+    fn shutdown_untracked(&self) -> Result<()> {
         let ret = unsafe { nanomsg_sys::nn_shutdown(self.socket, self.value) };
 
         if ret == -1 {
@@ -28,4 +127,150 @@ impl Endpoint {
             Ok(())
         }
     }
+
+    /// Wraps the endpoint in a guard that shuts it down when dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nanomsg::{Protocol, Socket};
+    ///
+    /// let mut socket = Socket::new(Protocol::Push).unwrap();
+    ///
+    /// {
+    ///     let _endpoint = socket.connect("ipc:///tmp/into_guard_doc.ipc").unwrap().into_guard();
+    ///     assert_eq!(1, socket.endpoints().len());
+    /// }
+    ///
+    /// assert!(socket.endpoints().is_empty());
+    /// ```
+    pub fn into_guard(self) -> EndpointGuard {
+        EndpointGuard {
+            endpoint: Some(self),
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    /// Formats the endpoint as `<kind> <address> (socket <socket id>, endpoint <id>)`.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} {} (socket {}, endpoint {})",
+            self.kind, self.addr, self.socket, self.value
+        )
+    }
+}
+
+/// An endpoint that is shut down when the guard goes out of scope.
+/// Errors happening during that shutdown are ignored, use `EndpointGuard::shutdown` to get them.
+///
+/// **See also:** `Endpoint::into_guard`
+#[derive(Debug)]
+pub struct EndpointGuard {
+    endpoint: Option<Endpoint>,
+}
+
+impl EndpointGuard {
+    /// Shuts the endpoint down now, returning the error the drop would have ignored.
+    pub fn shutdown(mut self) -> Result<()> {
+        match self.endpoint.take() {
+            Some(mut endpoint) => endpoint.shutdown(),
+            None => Ok(()),
+        }
+    }
+
+    /// Releases the endpoint without shutting it down, it will then live as long as its socket.
+    pub fn detach(mut self) -> Endpoint {
+        self.endpoint
+            .take()
+            .expect("the endpoint is only taken when the guard is consumed")
+    }
+}
+
+impl Deref for EndpointGuard {
+    type Target = Endpoint;
+
+    fn deref(&self) -> &Endpoint {
+        self.endpoint
+            .as_ref()
+            .expect("the endpoint is only taken when the guard is consumed")
+    }
+}
+
+impl From<Endpoint> for EndpointGuard {
+    fn from(endpoint: Endpoint) -> EndpointGuard {
+        endpoint.into_guard()
+    }
+}
+
+impl Drop for EndpointGuard {
+    /// Shuts the endpoint down, ignoring errors.
+    fn drop(&mut self) {
+        if let Some(mut endpoint) = self.endpoint.take() {
+            let _ = endpoint.shutdown();
+        }
+    }
+}
+
+/// The endpoints of a socket that have not been shut down yet.
+#[derive(Debug, Default)]
+pub(crate) struct EndpointRegistry {
+    endpoints: Mutex<Vec<Endpoint>>,
+    // Only changed while holding the lock.
+    closed: AtomicBool,
+}
+
+impl EndpointRegistry {
+    /// Locks the registry. The lock is never held while calling user code,
+    /// so a poisoned lock still holds a consistent list.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Vec<Endpoint>> {
+        self.endpoints
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Locks the registry unless its socket is closed.
+    /// The socket cannot be marked closed while the lock is held, so its id cannot be reused by another socket.
+    pub(crate) fn lock_open(&self) -> Option<MutexGuard<'_, Vec<Endpoint>>> {
+        let endpoints = self.lock();
+
+        if self.closed.load(Ordering::Relaxed) {
+            None
+        } else {
+            Some(endpoints)
+        }
+    }
+
+    /// Marks the socket closed, waiting for the endpoints and metrics collectors using it to release the lock,
+    /// then closes it. `nn_close` can block for the linger time, so it is called without holding the lock.
+    pub(crate) fn close(&self, socket: c_int) {
+        {
+            let mut endpoints = self.lock();
+
+            endpoints.clear();
+            self.closed.store(true, Ordering::Relaxed);
+        }
+
+        unsafe {
+            nanomsg_sys::nn_close(socket);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Endpoint, EndpointKind};
+    use crate::Address;
+
+    #[test]
+    fn endpoint_can_be_displayed() {
+        let addr = Address::Ipc("/tmp/endpoint_can_be_displayed.ipc".to_owned());
+        let endpoint = Endpoint::new(2, 1, addr, EndpointKind::Connect);
+
+        assert_eq!(
+            "connect ipc:///tmp/endpoint_can_be_displayed.ipc (socket 1, endpoint 2)",
+            endpoint.to_string()
+        );
+    }
 }
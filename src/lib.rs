@@ -1,22 +1,43 @@
 extern crate libc;
 extern crate nanomsg_sys;
 
-pub use endpoint::Endpoint;
+pub use address::Address;
+#[cfg(all(feature = "tokio", unix))]
+pub use async_socket::AsyncSocket;
+pub use builder::{BuildError, BuildStep, SocketBuilder};
+#[cfg(feature = "serde")]
+pub use config::SocketConfig;
+pub use endpoint::{Endpoint, EndpointGuard, EndpointKind};
+pub use message::Message;
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsCollector;
+pub use raw::{Backtrace, RawMessage};
 pub use result::{Error, Result};
-
-use nanomsg_sys::nn_pollfd;
-
+pub use statistics::Statistics;
+#[cfg(all(feature = "tokio_stream", unix))]
+pub use stream::Messages;
+pub use symbol::{symbols, Symbol, SymbolNamespace, SymbolType, SymbolUnit, Symbols};
+pub use typed::{
+    BusSocket, PairSocket, PubSocket, PullSocket, PushSocket, RecvSocket, RepSocket, ReqSocket,
+    RespondentSocket, SendSocket, SubSocket, SurveyorSocket,
+};
+pub use version::{version, Feature, Version};
+
+use nanomsg_sys::{nn_iovec, nn_msghdr, nn_pollfd};
+
+use endpoint::EndpointRegistry;
 use libc::{c_int, c_void, size_t};
 use result::last_nano_error;
 use std::cmp;
-use std::convert::From;
+use std::convert::{From, TryInto};
 use std::ffi::CString;
 use std::io;
-use std::mem;
 use std::mem::size_of;
 use std::ptr;
 use std::slice;
 use std::str;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(unix)]
 use std::os::unix::io::RawFd;
@@ -24,13 +45,33 @@ use std::os::unix::io::RawFd;
 #[cfg(windows)]
 use std::os::windows::raw::SOCKET;
 
+pub mod address;
+#[cfg(all(feature = "tokio", unix))]
+pub mod async_socket;
+pub mod builder;
+#[cfg(feature = "serde")]
+pub mod config;
 pub mod endpoint;
+pub mod message;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod raw;
 pub mod result;
+#[cfg(all(feature = "mio", unix))]
+mod source;
+pub mod statistics;
+#[cfg(all(feature = "tokio_stream", unix))]
+pub mod stream;
+pub mod symbol;
+pub mod typed;
+pub mod version;
 
 /// Type-safe protocols that Nanomsg uses. Each socket
 /// is bound to a single protocol that has specific behaviour
 /// (such as only being able to receive messages and not send them).
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Protocol {
     /// Used to implement the client application that sends requests and receives replies.
     ///
@@ -85,12 +126,42 @@ pub enum Protocol {
 }
 
 impl Protocol {
+    #[allow(clippy::wrong_self_convention)]
     fn to_raw(&self) -> c_int {
         *self as c_int
     }
+
+    fn from_raw(raw: c_int) -> Option<Protocol> {
+        match raw {
+            nanomsg_sys::NN_REQ => Some(Protocol::Req),
+            nanomsg_sys::NN_REP => Some(Protocol::Rep),
+            nanomsg_sys::NN_PUSH => Some(Protocol::Push),
+            nanomsg_sys::NN_PULL => Some(Protocol::Pull),
+            nanomsg_sys::NN_PAIR => Some(Protocol::Pair),
+            nanomsg_sys::NN_BUS => Some(Protocol::Bus),
+            nanomsg_sys::NN_PUB => Some(Protocol::Pub),
+            nanomsg_sys::NN_SUB => Some(Protocol::Sub),
+            nanomsg_sys::NN_SURVEYOR => Some(Protocol::Surveyor),
+            nanomsg_sys::NN_RESPONDENT => Some(Protocol::Respondent),
+            _ => None,
+        }
+    }
+}
+
+/// The domain of a socket, which tells whether it was created by `Socket::new`
+/// or by `Socket::new_for_device`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Domain {
+    /// Standard full-blown SP socket.
+    Sp = (nanomsg_sys::AF_SP) as isize,
+
+    /// Raw SP socket, omitting the end-to-end functionality, to be used in devices.
+    SpRaw = (nanomsg_sys::AF_SP_RAW) as isize,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Transport {
     /// In-process transport
     Inproc = (nanomsg_sys::NN_INPROC) as isize,
@@ -98,6 +169,8 @@ pub enum Transport {
     Ipc = (nanomsg_sys::NN_IPC) as isize,
     /// TCP transport
     Tcp = (nanomsg_sys::NN_TCP) as isize,
+    /// WebSocket transport
+    Ws = (nanomsg_sys::NN_WS) as isize,
 }
 
 impl Transport {
@@ -106,11 +179,25 @@ impl Transport {
     }
 }
 
+/// Type of the frames used by the WebSocket transport to send messages.
+///
+/// **See also:** `Socket::set_ws_msg_type`
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum WsMessageType {
+    /// Messages are sent as UTF-8 text frames, they must contain valid UTF-8.
+    Text = (nanomsg_sys::NN_WS_MSG_TYPE_TEXT) as isize,
+    /// Messages are sent as binary frames.
+    Binary = (nanomsg_sys::NN_WS_MSG_TYPE_BINARY) as isize,
+}
+
 /// A type-safe socket wrapper around nanomsg's own socket implementation. This
 /// provides a safe interface for dealing with initializing the sockets, sending
 /// and receiving messages.
 pub struct Socket {
     socket: c_int,
+    endpoints: Arc<EndpointRegistry>,
 }
 
 #[derive(Clone, Copy)]
@@ -179,6 +266,19 @@ impl<'a> PollRequest<'a> {
         self.fds
     }
 
+    /// Waits for the sockets of the request, see `Socket::poll_duration`.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The timeout does not fit in a `c_int` number of milliseconds.
+    /// - `BadFileDescriptor` : Some of the provided sockets are invalid.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
+    /// - `Timeout` : No event was signaled before the specified timeout.
+    /// - `Terminating` : The library is terminating.
+    pub fn poll_duration(&mut self, timeout: Option<Duration>) -> Result<usize> {
+        Socket::poll_duration(self, timeout)
+    }
+
     fn get_nn_fds(&mut self) -> *mut nn_pollfd {
         self.nn_fds.as_mut_ptr()
     }
@@ -209,6 +309,57 @@ macro_rules! io_error_guard(
     )
 );
 
+/// Converts a duration into the milliseconds expected by nanomsg, `None` meaning infinite.
+/// Sub-millisecond remainders are rounded up, so that a non-zero duration never means "do not wait".
+fn duration_to_millis(duration: Option<Duration>) -> Result<c_int> {
+    let duration = match duration {
+        None => return Ok(-1),
+        Some(duration) => duration,
+    };
+    let mut millis = duration.as_millis();
+
+    if duration.subsec_nanos() % 1_000_000 != 0 {
+        millis += 1;
+    }
+
+    if millis > c_int::MAX as u128 {
+        return Err(Error::InvalidInput);
+    }
+
+    Ok(millis as c_int)
+}
+
+/// Converts milliseconds returned by nanomsg into a duration, negative values meaning infinite.
+fn millis_to_duration(millis: c_int) -> Option<Duration> {
+    if millis < 0 {
+        None
+    } else {
+        Some(Duration::from_millis(millis as u64))
+    }
+}
+
+/// Converts milliseconds read from an option without an infinite value,
+/// a negative value set through an `isize` setter cannot be represented as a duration.
+fn millis_to_interval<T: TryInto<u64>>(millis: T) -> Result<Duration> {
+    millis
+        .try_into()
+        .map(Duration::from_millis)
+        .map_err(|_| Error::InvalidInput)
+}
+
+fn new_msghdr(iov: &mut [nn_iovec]) -> Result<nn_msghdr> {
+    if iov.len() > c_int::MAX as usize {
+        return Err(Error::InvalidInput);
+    }
+
+    Ok(nn_msghdr {
+        msg_iov: iov.as_mut_ptr(),
+        msg_iovlen: iov.len() as c_int,
+        msg_control: ptr::null_mut(),
+        msg_controllen: 0,
+    })
+}
+
 impl Socket {
     /// Allocate and initialize a new Nanomsg socket which returns
     /// a new file descriptor behind the scene. The safe interface doesn't
@@ -257,15 +408,18 @@ impl Socket {
         let socket = unsafe { nanomsg_sys::nn_socket(domain, protocol.to_raw()) };
 
         error_guard!(socket);
-        Ok(Socket { socket })
+        Ok(Socket {
+            socket,
+            endpoints: Arc::default(),
+        })
     }
 
     /// Creating a new socket through `Socket::new` does **not**
     /// bind that socket to a listening state. Instead, one has to be
     /// explicit in enabling the socket to listen onto a specific address.
     ///
-    /// That's what the `bind` method does. Passing in a raw string like:
-    /// "ipc:///tmp/pipeline.ipc" is supported.
+    /// That's what the `bind` method does. Either an `Address` or a raw string like:
+    /// "ipc:///tmp/pipeline.ipc" is supported, strings are parsed with `Address::from_str`.
     ///
     /// Note: This does **not** block the current task. That job
     /// is up to the user of the library by entering a loop.
@@ -294,20 +448,25 @@ impl Socket {
     /// - `InvalidArgument` : The syntax of the supplied address is invalid.
     /// - `NameTooLong` : The supplied address is too long.
     /// - `ProtocolNotSupported` : The requested transport protocol is not supported.
+    /// - `Unsupported` : The address uses the WebSocket transport, missing from the linked library.
     /// - `AddressNotAvailable` : The requested endpoint is not local.
     /// - `NoDevice` : Address specifies a nonexistent interface.
     /// - `AddressInUse` : The requested local endpoint is already in use.
     /// - `Terminating` : The library is terminating.
-    pub fn bind(&mut self, addr: &str) -> Result<Endpoint> {
-        match CString::new(addr.as_bytes()) {
-            Err(_) => Err(Error::from_raw(nanomsg_sys::EINVAL)),
-            Ok(c_addr) => {
-                let ret = unsafe { nanomsg_sys::nn_bind(self.socket, c_addr.as_ptr()) };
-
-                error_guard!(ret);
-                Ok(Endpoint::new(ret, self.socket))
-            }
+    pub fn bind<A>(&mut self, addr: A) -> Result<Endpoint>
+    where
+        A: TryInto<Address>,
+        Error: From<A::Error>,
+    {
+        let addr = addr.try_into()?;
+        if addr.transport() == Transport::Ws {
+            version::require(Feature::WebSocket)?;
         }
+        let c_addr = addr.to_c_string()?;
+        let ret = unsafe { nanomsg_sys::nn_bind(self.socket, c_addr.as_ptr()) };
+
+        error_guard!(ret);
+        Ok(Endpoint::new(ret, self.socket, addr, EndpointKind::Bind).register(&self.endpoints))
     }
 
     /// Connects the socket to a remote endpoint.
@@ -336,17 +495,144 @@ impl Socket {
     /// - `InvalidArgument` : The syntax of the supplied address is invalid.
     /// - `NameTooLong` : The supplied address is too long.
     /// - `ProtocolNotSupported` : The requested transport protocol is not supported.
+    /// - `Unsupported` : The address uses the WebSocket transport, missing from the linked library.
     /// - `NoDevice` : Address specifies a nonexistent interface.
     /// - `Terminating` : The library is terminating.
-    pub fn connect(&mut self, addr: &str) -> Result<Endpoint> {
-        match CString::new(addr.as_bytes()) {
-            Err(_) => Err(Error::from_raw(nanomsg_sys::EINVAL)),
-            Ok(c_addr) => {
-                let ret = unsafe { nanomsg_sys::nn_connect(self.socket, c_addr.as_ptr()) };
-                error_guard!(ret);
-                Ok(Endpoint::new(ret, self.socket))
+    pub fn connect<A>(&mut self, addr: A) -> Result<Endpoint>
+    where
+        A: TryInto<Address>,
+        Error: From<A::Error>,
+    {
+        let addr = addr.try_into()?;
+        if addr.transport() == Transport::Ws {
+            version::require(Feature::WebSocket)?;
+        }
+        let c_addr = addr.to_c_string()?;
+        let ret = unsafe { nanomsg_sys::nn_connect(self.socket, c_addr.as_ptr()) };
+
+        error_guard!(ret);
+        Ok(Endpoint::new(ret, self.socket, addr, EndpointKind::Connect).register(&self.endpoints))
+    }
+
+    /// Returns the endpoints of the socket that have not been shut down yet, in creation order.
+    /// Endpoints shut down through `Endpoint::shutdown` or the socket are removed from the list.
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        self.endpoints.lock().clone()
+    }
+
+    /// Shuts down every endpoint of the socket created with the specified address.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The address is invalid or no endpoint of the socket has this address.
+    /// - Any error returned by `Endpoint::shutdown`.
+    pub fn shutdown_address<A>(&mut self, addr: A) -> Result<()>
+    where
+        A: TryInto<Address>,
+        Error: From<A::Error>,
+    {
+        let addr = addr.try_into()?;
+        let matching: Vec<Endpoint> = self
+            .endpoints()
+            .into_iter()
+            .filter(|endpoint| *endpoint.address() == addr)
+            .collect();
+
+        if matching.is_empty() {
+            return Err(Error::InvalidInput);
+        }
+
+        for mut endpoint in matching {
+            endpoint.shutdown()?;
+        }
+
+        Ok(())
+    }
+
+    /// Shuts down all the endpoints of the socket.
+    /// Every endpoint is shut down even if some of them fail, the first error being returned.
+    pub fn shutdown_all(&mut self) -> Result<()> {
+        let mut result = Ok(());
+
+        for mut endpoint in self.endpoints() {
+            let shutdown = endpoint.shutdown();
+            result = result.and(shutdown);
+        }
+
+        result
+    }
+
+    /// Makes the connected endpoints of the socket match the specified addresses:
+    /// connects to the addresses that have no endpoint yet, then shuts down the connected
+    /// endpoints whose address is not in the list. Bound endpoints are left untouched.
+    ///
+    /// All the addresses are validated and connected before anything is shut down.
+    /// If a connection fails, the endpoints created by this call are shut down
+    /// and the error is returned, leaving the socket as it was.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nanomsg::{Address, Protocol, Socket};
+    ///
+    /// let mut socket = Socket::new(Protocol::Sub).unwrap();
+    /// let publishers: Vec<Address> = vec![
+    ///     "tcp://127.0.0.1:5501".parse().unwrap(),
+    ///     "tcp://127.0.0.1:5502".parse().unwrap(),
+    /// ];
+    ///
+    /// socket.reconcile(&publishers).unwrap();
+    /// socket.reconcile(&publishers[1..]).unwrap();
+    ///
+    /// assert_eq!(1, socket.endpoints().len());
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - Any error returned by `Address::validate`, `Socket::connect` or `Endpoint::shutdown`.
+    pub fn reconcile(&mut self, addrs: &[Address]) -> Result<()> {
+        for addr in addrs {
+            addr.validate()?;
+        }
+
+        let connected: Vec<Endpoint> = self
+            .endpoints()
+            .into_iter()
+            .filter(|endpoint| endpoint.kind() == EndpointKind::Connect)
+            .collect();
+        let mut added: Vec<Endpoint> = Vec::new();
+
+        for addr in addrs {
+            let exists = connected
+                .iter()
+                .chain(added.iter())
+                .any(|endpoint| endpoint.address() == addr);
+
+            if exists {
+                continue;
+            }
+
+            match self.connect(addr) {
+                Ok(endpoint) => added.push(endpoint),
+                Err(err) => {
+                    for mut endpoint in added {
+                        let _ = endpoint.shutdown();
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let mut result = Ok(());
+
+        for mut endpoint in connected {
+            if !addrs.contains(endpoint.address()) {
+                let shutdown = endpoint.shutdown();
+                result = result.and(shutdown);
             }
         }
+
+        result
     }
 
     /// Non-blocking version of the `read` function.
@@ -434,23 +720,10 @@ impl Socket {
     /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
     /// - `Terminating` : The library is terminating.
     pub fn nb_read_to_end(&self, buf: &mut Vec<u8>) -> Result<usize> {
-        let mut msg: *mut u8 = ptr::null_mut();
-        let ret = unsafe {
-            nanomsg_sys::nn_recv(
-                self.socket,
-                mem::transmute(&mut msg),
-                nanomsg_sys::NN_MSG,
-                nanomsg_sys::NN_DONTWAIT,
-            )
-        };
-
-        error_guard!(ret);
+        let msg = self.recv_msg_with_flags(nanomsg_sys::NN_DONTWAIT)?;
 
-        let ret = ret as usize;
-        let bytes = unsafe { slice::from_raw_parts(msg, ret) };
-        buf.extend_from_slice(bytes);
-        unsafe { nanomsg_sys::nn_freemsg(msg as *mut c_void) };
-        Ok(ret)
+        buf.extend_from_slice(&msg);
+        Ok(msg.len())
     }
 
     /// Non-blocking version of the `write` function.
@@ -545,6 +818,7 @@ impl Socket {
     /// While `write` functions allow to send arbitrary buffers,
     /// buffers allocated using `allocate_msg` can be more efficient for large messages
     /// as they allow for using zero-copy techniques.
+    /// See `Message` for an owned alternative that is freed automatically.
     ///
     /// # Error
     ///
@@ -578,6 +852,283 @@ impl Socket {
         }
     }
 
+    /// Sends a message allocated by nanomsg without copying it.
+    /// The message ownership is transferred to nanomsg, which will deallocate it once sent.
+    /// If the message cannot be sent, it is dropped and the error is returned.
+    /// Returns the number of bytes sent on success.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use nanomsg::{Socket, Protocol, Message};
+    ///
+    /// let mut push_socket = Socket::new(Protocol::Push).unwrap();
+    /// let mut push_endpoint = push_socket.bind("ipc:///tmp/send_msg_doc.ipc").unwrap();
+    /// let mut pull_socket = Socket::new(Protocol::Pull).unwrap();
+    /// let mut pull_endpoint = pull_socket.connect("ipc:///tmp/send_msg_doc.ipc").unwrap();
+    /// let msg = Message::from_slice(b"foobar").unwrap();
+    ///
+    /// match push_socket.send_msg(msg) {
+    ///     Ok(_) => { println!("Message sent !"); },
+    ///     Err(err) => panic!("Problem while writing: {}", err)
+    /// };
+    ///
+    /// match pull_socket.recv_msg() {
+    ///     Ok(msg) => { println!("Message received: {:?}", &msg[..]); },
+    ///     Err(err) => panic!("Problem while reading: {}", err)
+    /// };
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was sent.
+    /// - `TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
+    /// - `Terminating` : The library is terminating.
+    pub fn send_msg(&self, msg: Message) -> Result<usize> {
+        self.send_msg_with_flags(msg, 0).map_err(|(err, _)| err)
+    }
+
+    /// Receives a message allocated by nanomsg, without copying it into a user buffer.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
+    /// - `Terminating` : The library is terminating.
+    pub fn recv_msg(&self) -> Result<Message> {
+        self.recv_msg_with_flags(0)
+    }
+
+    /// Non-blocking version of the `recv_msg` function.
+    /// An error with the `Error::TryAgain` kind is returned if there's no message to receive for the moment.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `TryAgain` : Non-blocking mode was requested and there’s no message to receive at the moment.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
+    /// - `Terminating` : The library is terminating.
+    pub fn nb_recv_msg(&self) -> Result<Message> {
+        self.recv_msg_with_flags(nanomsg_sys::NN_DONTWAIT)
+    }
+
+    /// Sends the message with `NN_MSG`, giving it back to the caller when nanomsg did not take ownership.
+    fn send_msg_with_flags(
+        &self,
+        mut msg: Message,
+        flags: c_int,
+    ) -> std::result::Result<usize, (Error, Message)> {
+        // nanomsg sends the whole allocated chunk, spare capacity must be released first
+        if let Err(err) = msg.shrink_to_fit() {
+            return Err((err, msg));
+        }
+
+        let (ptr, len) = msg.into_raw();
+        let ptr_addr = &ptr as *const *mut u8 as *const c_void;
+        let ret =
+            unsafe { nanomsg_sys::nn_send(self.socket, ptr_addr, nanomsg_sys::NN_MSG, flags) };
+
+        if ret == -1 {
+            let msg = unsafe { Message::from_raw(ptr, len) };
+            return Err((last_nano_error(), msg));
+        }
+
+        Ok(ret as usize)
+    }
+
+    fn recv_msg_with_flags(&self, flags: c_int) -> Result<Message> {
+        let mut ptr: *mut u8 = ptr::null_mut();
+        let ptr_addr = &mut ptr as *mut *mut u8 as *mut c_void;
+        let ret =
+            unsafe { nanomsg_sys::nn_recv(self.socket, ptr_addr, nanomsg_sys::NN_MSG, flags) };
+
+        error_guard!(ret);
+        Ok(unsafe { Message::from_raw(ptr, ret as usize) })
+    }
+
+    /// Sends a single message made of the concatenation of the specified buffers.
+    /// The buffers are handed to nanomsg as an array of `nn_iovec`, so they are not copied into an intermediate buffer.
+    /// Returns the number of bytes sent on success.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use nanomsg::{Socket, Protocol};
+    /// use std::io::{IoSlice, IoSliceMut};
+    ///
+    /// let mut push_socket = Socket::new(Protocol::Push).unwrap();
+    /// let mut push_endpoint = push_socket.bind("ipc:///tmp/send_vectored_doc.ipc").unwrap();
+    /// let mut pull_socket = Socket::new(Protocol::Pull).unwrap();
+    /// let mut pull_endpoint = pull_socket.connect("ipc:///tmp/send_vectored_doc.ipc").unwrap();
+    ///
+    /// let header = [1u8, 0u8];
+    /// let payload = b"foobar";
+    /// push_socket.send_vectored(&[IoSlice::new(&header), IoSlice::new(payload)]).unwrap();
+    ///
+    /// let mut header = [0u8; 2];
+    /// let mut payload = [0u8; 1024];
+    /// let count = pull_socket
+    ///     .recv_vectored(&mut [IoSliceMut::new(&mut header), IoSliceMut::new(&mut payload)])
+    ///     .unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `InvalidInput` : Too many buffers were specified.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was sent.
+    /// - `TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
+    /// - `Terminating` : The library is terminating.
+    pub fn send_vectored(&self, bufs: &[io::IoSlice]) -> Result<usize> {
+        let mut iov: Vec<nn_iovec> = bufs
+            .iter()
+            .map(|buf| nn_iovec {
+                iov_base: buf.as_ptr() as *mut c_void,
+                iov_len: buf.len() as size_t,
+            })
+            .collect();
+        let hdr = new_msghdr(&mut iov)?;
+        let ret = unsafe { nanomsg_sys::nn_sendmsg(self.socket, &hdr, 0) };
+
+        error_guard!(ret);
+        Ok(ret as usize)
+    }
+
+    /// Receives a message and scatters it into the specified buffers, filling them in order.
+    /// Any bytes exceeding the total length of the buffers will be truncated.
+    /// Returns the number of bytes of the message stored in the buffers on success.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `InvalidInput` : Too many buffers were specified.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
+    /// - `Terminating` : The library is terminating.
+    pub fn recv_vectored(&self, bufs: &mut [io::IoSliceMut]) -> Result<usize> {
+        let buf_len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let mut iov: Vec<nn_iovec> = bufs
+            .iter_mut()
+            .map(|buf| nn_iovec {
+                iov_base: buf.as_mut_ptr() as *mut c_void,
+                iov_len: buf.len() as size_t,
+            })
+            .collect();
+        let mut hdr = new_msghdr(&mut iov)?;
+        let ret = unsafe { nanomsg_sys::nn_recvmsg(self.socket, &mut hdr, 0) };
+
+        error_guard!(ret);
+        Ok(cmp::min(ret as usize, buf_len))
+    }
+
+    /// Receives a message together with its SP header.
+    /// This is meant to be used on raw sockets created with `Socket::new_for_device`,
+    /// in order to implement custom devices that inspect or rewrite the request ids.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use nanomsg::{Socket, Protocol};
+    ///
+    /// let mut front = Socket::new_for_device(Protocol::Rep).unwrap();
+    /// let mut front_ep = front.bind("ipc:///tmp/recv_raw_msg_doc.ipc").unwrap();
+    ///
+    /// // Wait for a request, then forward it with its backtrace to some worker ...
+    /// let request = front.recv_raw_msg().unwrap();
+    /// let backtrace = request.backtrace().unwrap();
+    /// println!("Request {} came through pipe {:?}", backtrace.request_id(), backtrace.hops());
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
+    /// - `Terminating` : The library is terminating.
+    pub fn recv_raw_msg(&self) -> Result<RawMessage> {
+        let mut body: *mut u8 = ptr::null_mut();
+        let mut control: *mut u8 = ptr::null_mut();
+        let mut iov = [nn_iovec {
+            iov_base: &mut body as *mut *mut u8 as *mut c_void,
+            iov_len: nanomsg_sys::NN_MSG,
+        }];
+        let mut hdr = nn_msghdr {
+            msg_iov: iov.as_mut_ptr(),
+            msg_iovlen: 1,
+            msg_control: &mut control as *mut *mut u8 as *mut c_void,
+            msg_controllen: nanomsg_sys::NN_MSG,
+        };
+        let ret = unsafe { nanomsg_sys::nn_recvmsg(self.socket, &mut hdr, 0) };
+
+        error_guard!(ret);
+
+        let body = unsafe { Message::from_raw(body, ret as usize) };
+        let header = unsafe { raw::read_sp_header(control) };
+
+        if !control.is_null() {
+            unsafe { nanomsg_sys::nn_freemsg(control as *mut c_void) };
+        }
+
+        Ok(RawMessage::new(header, body))
+    }
+
+    /// Sends a message together with its SP header.
+    /// On a raw `Rep` or `Respondent` socket, the header must be the one of the request being answered,
+    /// so the reply can be routed back to the requester.
+    /// The body ownership is transferred to nanomsg like with `Socket::send_msg`.
+    /// Returns the number of bytes of the body sent on success.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `OperationNotSupported` : The operation is not supported by this socket type.
+    /// - `FileStateMismatch` : The operation cannot be performed on this socket at the moment because socket is not in the appropriate state. This error may occur with socket types that switch between several states.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was sent.
+    /// - `TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
+    /// - `Terminating` : The library is terminating.
+    pub fn send_raw_msg(&self, msg: RawMessage) -> Result<usize> {
+        let (header, mut body) = msg.into_parts();
+
+        // nanomsg sends the whole allocated chunk, spare capacity must be released first
+        body.shrink_to_fit()?;
+
+        let mut control = raw::write_sp_header(&header);
+        let (mut body, len) = body.into_raw();
+        let mut iov = [nn_iovec {
+            iov_base: &mut body as *mut *mut u8 as *mut c_void,
+            iov_len: nanomsg_sys::NN_MSG,
+        }];
+        let hdr = nn_msghdr {
+            msg_iov: iov.as_mut_ptr(),
+            msg_iovlen: 1,
+            msg_control: control.as_mut_ptr() as *mut c_void,
+            msg_controllen: control.len() * size_of::<size_t>(),
+        };
+        let ret = unsafe { nanomsg_sys::nn_sendmsg(self.socket, &hdr, 0) };
+
+        if ret == -1 {
+            // nanomsg did not take ownership of the body
+            drop(unsafe { Message::from_raw(body, len) });
+            return Err(last_nano_error());
+        }
+
+        Ok(ret as usize)
+    }
+
     /// Creates a poll request for the socket with the specified check criteria.
     /// - **pollinout:** See `PollInOut` for options
     pub fn new_pollfd(&self, pollinout: PollInOut) -> PollFd {
@@ -644,6 +1195,21 @@ impl Socket {
         Ok(ret as usize)
     }
 
+    /// Same as `Socket::poll`, with a timeout expressed as a duration, `None` meaning infinite.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The timeout does not fit in a `c_int` number of milliseconds.
+    /// - `BadFileDescriptor` : Some of the provided sockets are invalid.
+    /// - `Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
+    /// - `Timeout` : No event was signaled before the specified timeout.
+    /// - `Terminating` : The library is terminating.
+    pub fn poll_duration(request: &mut PollRequest, timeout: Option<Duration>) -> Result<usize> {
+        let timeout = duration_to_millis(timeout)?;
+
+        Socket::poll(request, timeout as isize)
+    }
+
     /// Starts a device to forward messages between two sockets.
     /// If both sockets are valid, `device` function loops
     /// and sends and messages received from s1 to s2 and vice versa.
@@ -907,6 +1473,37 @@ impl Socket {
         )
     }
 
+    /// Sets the type of the frames used to send messages over the WebSocket transport.
+    /// It only applies to the `ws://` endpoints added afterwards.
+    /// Default value is `WsMessageType::Binary`.
+    ///
+    /// # Error
+    ///
+    /// - `Unsupported` : The linked library has no WebSocket transport.
+    pub fn set_ws_msg_type(&mut self, msg_type: WsMessageType) -> Result<()> {
+        version::require(Feature::WebSocket)?;
+        self.set_socket_options_c_int(
+            nanomsg_sys::NN_WS,
+            nanomsg_sys::NN_WS_MSG_TYPE,
+            msg_type as c_int,
+        )
+    }
+
+    /// Returns the type of the frames used to send messages over the WebSocket transport.
+    ///
+    /// **See also:** `Socket::set_ws_msg_type`
+    pub fn get_ws_msg_type(&self) -> Result<WsMessageType> {
+        version::require(Feature::WebSocket)?;
+        let msg_type =
+            self.get_socket_option_c_int(nanomsg_sys::NN_WS, nanomsg_sys::NN_WS_MSG_TYPE)?;
+
+        match msg_type {
+            nanomsg_sys::NN_WS_MSG_TYPE_TEXT => Ok(WsMessageType::Text),
+            nanomsg_sys::NN_WS_MSG_TYPE_BINARY => Ok(WsMessageType::Binary),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+
     /// Retrieve a file descriptor that is readable when a message can
     /// be received on the unerlying socket
     #[cfg(unix)]
@@ -944,14 +1541,270 @@ impl Socket {
             .map(|v: CString| v.to_string_lossy().into_owned())
     }
 
-    /// Defined on full `Sub` socket.
-    /// Subscribes for a particular topic.
-    /// A single `Sub` socket can handle multiple subscriptions.
-    pub fn subscribe(&mut self, topic: &[u8]) -> Result<()> {
-        self.set_socket_options_buf(nanomsg_sys::NN_SUB, nanomsg_sys::NN_SUB_SUBSCRIBE, topic)
+    /// Returns how long the socket should try to send pending outbound messages after `drop` have been called.
+    /// Negative value means infinite linger.
+    ///
+    /// **See also:** `Socket::set_linger`
+    pub fn get_linger(&self) -> Result<isize> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_LINGER)
+            .map(|v| v as isize)
     }
 
-    /// Defined on full `Sub` socket. Unsubscribes from a particular topic.
+    /// Returns the size of the send buffer, in bytes.
+    ///
+    /// **See also:** `Socket::set_send_buffer_size`
+    pub fn get_send_buffer_size(&self) -> Result<usize> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_SNDBUF)
+            .map(|v| v as usize)
+    }
+
+    /// Returns the size of the receive buffer, in bytes.
+    ///
+    /// **See also:** `Socket::set_receive_buffer_size`
+    pub fn get_receive_buffer_size(&self) -> Result<usize> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_RCVBUF)
+            .map(|v| v as usize)
+    }
+
+    /// Returns the maximum message size that can be received, in bytes.
+    /// Negative value means that the received size is limited only by available addressable memory.
+    ///
+    /// **See also:** `Socket::set_receive_max_size`
+    pub fn get_receive_max_size(&self) -> Result<isize> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_RCVMAXSIZE)
+            .map(|v| v as isize)
+    }
+
+    /// Returns the timeout for send operation on the socket, in milliseconds.
+    /// Negative value means infinite timeout.
+    ///
+    /// **See also:** `Socket::set_send_timeout`
+    pub fn get_send_timeout(&self) -> Result<isize> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_SNDTIMEO)
+            .map(|v| v as isize)
+    }
+
+    /// Returns the timeout for recv operation on the socket, in milliseconds.
+    /// Negative value means infinite timeout.
+    ///
+    /// **See also:** `Socket::set_receive_timeout`
+    pub fn get_receive_timeout(&self) -> Result<isize> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_RCVTIMEO)
+            .map(|v| v as isize)
+    }
+
+    /// Returns how long to wait, in milliseconds, before trying to re-establish a broken connection.
+    ///
+    /// **See also:** `Socket::set_reconnect_interval`
+    pub fn get_reconnect_interval(&self) -> Result<isize> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_RECONNECT_IVL)
+            .map(|v| v as isize)
+    }
+
+    /// Returns the maximum reconnection interval, in milliseconds.
+    ///
+    /// **See also:** `Socket::set_max_reconnect_interval`
+    pub fn get_max_reconnect_interval(&self) -> Result<isize> {
+        self.get_socket_option_c_int(
+            nanomsg_sys::NN_SOL_SOCKET,
+            nanomsg_sys::NN_RECONNECT_IVL_MAX,
+        )
+        .map(|v| v as isize)
+    }
+
+    /// Returns the outbound priority for endpoints subsequently added to the socket.
+    ///
+    /// **See also:** `Socket::set_send_priority`
+    pub fn get_send_priority(&self) -> Result<u8> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_SNDPRIO)
+            .map(|v| v as u8)
+    }
+
+    /// Returns the inbound priority for endpoints subsequently added to the socket.
+    ///
+    /// **See also:** `Socket::set_receive_priority`
+    pub fn get_receive_priority(&self) -> Result<u8> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_RCVPRIO)
+            .map(|v| v as u8)
+    }
+
+    /// Returns whether only IPv4 addresses are used.
+    ///
+    /// **See also:** `Socket::set_ipv4_only`
+    pub fn get_ipv4_only(&self) -> Result<bool> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_IPV4ONLY)
+            .map(|v| v != 0)
+    }
+
+    /// Sets the maximum number of "hops" a message can go through before it is dropped.
+    /// Each time the message is received (for example via the `device` function) counts as a single hop.
+    /// This provides a form of protection against inadvertent loops.
+    /// Accepted values are between 1 and 255. Default value is 8.
+    ///
+    /// # Error
+    ///
+    /// - `Unsupported` : The linked library does not have this option.
+    pub fn set_max_ttl(&mut self, ttl: u8) -> Result<()> {
+        version::require(Feature::MaxTtl)?;
+        self.set_socket_options_c_int(
+            nanomsg_sys::NN_SOL_SOCKET,
+            nanomsg_sys::NN_MAXTTL,
+            ttl as c_int,
+        )
+    }
+
+    /// Returns the maximum number of "hops" a message can go through before it is dropped.
+    ///
+    /// **See also:** `Socket::set_max_ttl`
+    pub fn get_max_ttl(&self) -> Result<u8> {
+        version::require(Feature::MaxTtl)?;
+        self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_MAXTTL)
+            .map(|v| v as u8)
+    }
+
+    /// Returns the domain of the socket, telling whether it is a raw socket or not.
+    pub fn get_domain(&self) -> Result<Domain> {
+        let domain =
+            self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_DOMAIN)?;
+
+        match domain {
+            nanomsg_sys::AF_SP => Ok(Domain::Sp),
+            nanomsg_sys::AF_SP_RAW => Ok(Domain::SpRaw),
+            _ => Err(Error::AddressFamilyNotSupported),
+        }
+    }
+
+    /// Returns the protocol the socket was created with.
+    pub fn get_protocol(&self) -> Result<Protocol> {
+        let protocol =
+            self.get_socket_option_c_int(nanomsg_sys::NN_SOL_SOCKET, nanomsg_sys::NN_PROTOCOL)?;
+
+        Protocol::from_raw(protocol).ok_or(Error::ProtocolNotSupported)
+    }
+
+    /// Takes a snapshot of the statistics of the socket.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nanomsg::{Protocol, Socket};
+    ///
+    /// let socket = Socket::new(Protocol::Push).unwrap();
+    /// let before = socket.statistics().unwrap();
+    /// // ... use the socket ...
+    /// let delta = socket.statistics().unwrap().since(&before);
+    ///
+    /// println!("{} messages sent", delta.messages_sent);
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    /// - `Unsupported` : The linked library does not collect statistics.
+    pub fn statistics(&self) -> Result<Statistics> {
+        statistics::read(self.socket)
+    }
+
+    /// Same as `Socket::set_linger`, `None` meaning infinite linger.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The duration does not fit in a `c_int` number of milliseconds.
+    pub fn set_linger_duration(&mut self, linger: Option<Duration>) -> Result<()> {
+        let linger = duration_to_millis(linger)?;
+
+        self.set_linger(linger as isize)
+    }
+
+    /// Same as `Socket::get_linger`, `None` meaning infinite linger.
+    pub fn get_linger_duration(&self) -> Result<Option<Duration>> {
+        self.get_linger().map(|v| millis_to_duration(v as c_int))
+    }
+
+    /// Same as `Socket::set_send_timeout`, `None` meaning infinite timeout.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The duration does not fit in a `c_int` number of milliseconds.
+    pub fn set_send_timeout_duration(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let timeout = duration_to_millis(timeout)?;
+
+        self.set_send_timeout(timeout as isize)
+    }
+
+    /// Same as `Socket::get_send_timeout`, `None` meaning infinite timeout.
+    pub fn get_send_timeout_duration(&self) -> Result<Option<Duration>> {
+        self.get_send_timeout()
+            .map(|v| millis_to_duration(v as c_int))
+    }
+
+    /// Same as `Socket::set_receive_timeout`, `None` meaning infinite timeout.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The duration does not fit in a `c_int` number of milliseconds.
+    pub fn set_receive_timeout_duration(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let timeout = duration_to_millis(timeout)?;
+
+        self.set_receive_timeout(timeout as isize)
+    }
+
+    /// Same as `Socket::get_receive_timeout`, `None` meaning infinite timeout.
+    pub fn get_receive_timeout_duration(&self) -> Result<Option<Duration>> {
+        self.get_receive_timeout()
+            .map(|v| millis_to_duration(v as c_int))
+    }
+
+    /// Same as `Socket::set_reconnect_interval`.
+    /// There is no infinite reconnect interval, hence the plain duration.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The duration does not fit in a `c_int` number of milliseconds.
+    pub fn set_reconnect_interval_duration(&mut self, interval: Duration) -> Result<()> {
+        let interval = duration_to_millis(Some(interval))?;
+
+        self.set_reconnect_interval(interval as isize)
+    }
+
+    /// Same as `Socket::get_reconnect_interval`.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The option holds a negative value, set through the `isize` setter.
+    pub fn get_reconnect_interval_duration(&self) -> Result<Duration> {
+        self.get_reconnect_interval().and_then(millis_to_interval)
+    }
+
+    /// Same as `Socket::set_max_reconnect_interval`, a zero duration disabling the exponential backoff.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The duration does not fit in a `c_int` number of milliseconds.
+    pub fn set_max_reconnect_interval_duration(&mut self, interval: Duration) -> Result<()> {
+        let interval = duration_to_millis(Some(interval))?;
+
+        self.set_max_reconnect_interval(interval as isize)
+    }
+
+    /// Same as `Socket::get_max_reconnect_interval`.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The option holds a negative value, set through the `isize` setter.
+    pub fn get_max_reconnect_interval_duration(&self) -> Result<Duration> {
+        self.get_max_reconnect_interval()
+            .and_then(millis_to_interval)
+    }
+
+    /// Defined on full `Sub` socket.
+    /// Subscribes for a particular topic.
+    /// A single `Sub` socket can handle multiple subscriptions.
+    pub fn subscribe(&mut self, topic: &[u8]) -> Result<()> {
+        self.set_socket_options_buf(nanomsg_sys::NN_SUB, nanomsg_sys::NN_SUB_SUBSCRIBE, topic)
+    }
+
+    /// Defined on full `Sub` socket. Unsubscribes from a particular topic.
     pub fn unsubscribe(&mut self, topic: &[u8]) -> Result<()> {
         self.set_socket_options_buf(nanomsg_sys::NN_SUB, nanomsg_sys::NN_SUB_UNSUBSCRIBE, topic)
     }
@@ -967,6 +1820,29 @@ impl Socket {
         )
     }
 
+    /// Same as `Socket::set_survey_deadline`, with the deadline expressed as a duration.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The duration does not fit in a `c_int` number of milliseconds.
+    pub fn set_survey_deadline_duration(&mut self, deadline: Duration) -> Result<()> {
+        let deadline = duration_to_millis(Some(deadline))?;
+
+        self.set_survey_deadline(deadline as isize)
+    }
+
+    /// Returns how long to wait for responses to the survey.
+    ///
+    /// **See also:** `Socket::set_survey_deadline_duration`
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The option holds a negative value, set through the `isize` setter.
+    pub fn get_survey_deadline_duration(&self) -> Result<Duration> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_SURVEYOR, nanomsg_sys::NN_SURVEYOR_DEADLINE)
+            .and_then(millis_to_interval)
+    }
+
     /// This option is defined on the full `Req` socket.
     /// If reply is not received in specified amount of milliseconds, the request will be automatically resent.
     /// The type of this option is int. Default value is 1 minute.
@@ -977,6 +1853,29 @@ impl Socket {
             interval as c_int,
         )
     }
+
+    /// Same as `Socket::set_request_resend_interval`, with the interval expressed as a duration.
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The duration does not fit in a `c_int` number of milliseconds.
+    pub fn set_request_resend_interval_duration(&mut self, interval: Duration) -> Result<()> {
+        let interval = duration_to_millis(Some(interval))?;
+
+        self.set_request_resend_interval(interval as isize)
+    }
+
+    /// Returns how long to wait for a reply before resending the request.
+    ///
+    /// **See also:** `Socket::set_request_resend_interval_duration`
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The option holds a negative value, set through the `isize` setter.
+    pub fn get_request_resend_interval_duration(&self) -> Result<Duration> {
+        self.get_socket_option_c_int(nanomsg_sys::NN_REQ, nanomsg_sys::NN_REQ_RESEND_IVL)
+            .and_then(millis_to_interval)
+    }
 }
 
 impl io::Read for Socket {
@@ -1035,6 +1934,11 @@ impl io::Read for Socket {
         Ok(cmp::min(ret as usize, buf_len))
     }
 
+    /// Receive a message from the socket and scatter it into the buffers, see `Socket::recv_vectored`.
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut]) -> io::Result<usize> {
+        Ok(self.recv_vectored(bufs)?)
+    }
+
     /// Receive a message from the socket. Copy the message allocated by nanomsg into the buffer on success.
     ///
     /// # Example:
@@ -1076,23 +1980,10 @@ impl io::Read for Socket {
     /// - `io::ErrorKind::Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
     /// - `io::ErrorKind::Other` : The library is terminating.
     fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
-        let mut msg: *mut u8 = ptr::null_mut();
-        let ret = unsafe {
-            nanomsg_sys::nn_recv(
-                self.socket,
-                mem::transmute(&mut msg),
-                nanomsg_sys::NN_MSG,
-                0,
-            )
-        };
+        let msg = self.recv_msg_with_flags(0)?;
 
-        io_error_guard!(ret);
-
-        let ret = ret as usize;
-        let bytes = unsafe { slice::from_raw_parts(msg, ret) };
-        buf.extend_from_slice(bytes);
-        unsafe { nanomsg_sys::nn_freemsg(msg as *mut c_void) };
-        Ok(ret)
+        buf.extend_from_slice(&msg);
+        Ok(msg.len())
     }
 
     /// Receive a message from the socket. Copy the message allocated by nanomsg into the buffer on success.
@@ -1136,36 +2027,19 @@ impl io::Read for Socket {
     /// - `io::ErrorKind::TimedOut` : Individual socket types may define their own specific timeouts. If such timeout is hit this error will be returned.
     /// - `io::ErrorKind::Interrupted` : The operation was interrupted by delivery of a signal before the message was received.
     /// - `io::ErrorKind::Other` : The library is terminating, or the message is not a valid UTF-8 string.
+    #[allow(clippy::io_other_error)]
     fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
-        let mut msg: *mut u8 = ptr::null_mut();
-        let ret = unsafe {
-            nanomsg_sys::nn_recv(
-                self.socket,
-                mem::transmute(&mut msg),
-                nanomsg_sys::NN_MSG,
-                0,
-            )
-        };
-
-        io_error_guard!(ret);
+        let msg = self.recv_msg_with_flags(0)?;
 
-        unsafe {
-            let ret = ret as usize;
-            let bytes = slice::from_raw_parts(msg, ret);
-            match str::from_utf8(bytes) {
-                Ok(text) => {
-                    buf.push_str(text);
-                    nanomsg_sys::nn_freemsg(msg as *mut c_void);
-                    Ok(ret)
-                }
-                Err(_) => {
-                    nanomsg_sys::nn_freemsg(msg as *mut c_void);
-                    Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        "UTF8 conversion failed !",
-                    ))
-                }
+        match str::from_utf8(&msg) {
+            Ok(text) => {
+                buf.push_str(text);
+                Ok(msg.len())
             }
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "UTF8 conversion failed !",
+            )),
         }
     }
 }
@@ -1222,6 +2096,11 @@ impl io::Write for Socket {
         Ok(buf_len as usize)
     }
 
+    /// Send a single message made of all the buffers, see `Socket::send_vectored`.
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        Ok(self.send_vectored(bufs)?)
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
@@ -1233,9 +2112,7 @@ impl Drop for Socket {
     /// The library will try to deliver any outstanding outbound messages for the time specified by `set_linger`.
     /// The call will block in the meantime.
     fn drop(&mut self) {
-        unsafe {
-            nanomsg_sys::nn_close(self.socket);
-        }
+        self.endpoints.close(self.socket);
     }
 }
 
@@ -1243,11 +2120,16 @@ impl Drop for Socket {
 mod tests {
     #![allow(unused_must_use)]
     use super::Protocol::*;
-    use crate::{Endpoint, Error, PollFd, PollInOut, PollRequest, Protocol, Socket};
+    use crate::{
+        duration_to_millis, millis_to_duration, millis_to_interval, Address, Domain, Endpoint,
+        EndpointKind, Error, Message, PollFd, PollInOut, PollRequest, Protocol, RawMessage, Socket,
+        WsMessageType,
+    };
     use libc::c_int;
+    #[allow(clippy::single_component_path_imports)]
     use nanomsg_sys;
 
-    use std::io::{Read, Write};
+    use std::io::{IoSlice, IoSliceMut, Read, Write};
 
     use std::sync::{Arc, Barrier};
     use std::thread;
@@ -1330,6 +2212,196 @@ mod tests {
         drop(socket);
     }
 
+    #[test]
+    fn bind_and_connect_with_address() {
+        let addr = Address::Inproc("bind_and_connect_with_address".to_owned());
+
+        let mut push_socket = test_create_socket(Push);
+        push_socket.bind(&addr).unwrap();
+
+        let mut pull_socket = test_create_socket(Pull);
+        pull_socket.connect(addr).unwrap();
+
+        test_write(&mut push_socket, b"foobar");
+        test_read(&mut pull_socket, b"foobar");
+    }
+
+    #[test]
+    fn endpoint_remembers_address_and_kind() {
+        let url = "ipc:///tmp/endpoint_remembers_address_and_kind.ipc";
+
+        let mut push_socket = test_create_socket(Push);
+        let bound = test_bind(&mut push_socket, url);
+
+        let mut pull_socket = test_create_socket(Pull);
+        let connected = test_connect(&mut pull_socket, url);
+
+        assert_eq!(EndpointKind::Bind, bound.kind());
+        assert_eq!(EndpointKind::Connect, connected.kind());
+        assert_eq!(url, bound.address().to_string());
+        assert_eq!(url, connected.address().to_string());
+        assert_eq!(push_socket.socket, bound.socket_id());
+        assert_eq!(pull_socket.socket, connected.socket_id());
+    }
+
+    #[test]
+    fn socket_tracks_its_endpoints() {
+        let mut socket = test_create_socket(Pair);
+        let mut first = test_bind(&mut socket, "ipc:///tmp/socket_tracks_its_endpoints1.ipc");
+        test_bind(&mut socket, "ipc:///tmp/socket_tracks_its_endpoints2.ipc");
+        test_connect(&mut socket, "ipc:///tmp/socket_tracks_its_endpoints3.ipc");
+
+        assert_eq!(3, socket.endpoints().len());
+
+        first.shutdown().unwrap();
+        let ids: Vec<c_int> = socket.endpoints().iter().map(Endpoint::id).collect();
+        assert!(!ids.contains(&first.id()));
+        assert_eq!(2, ids.len());
+
+        socket
+            .shutdown_address("ipc:///tmp/socket_tracks_its_endpoints3.ipc")
+            .unwrap();
+        assert_eq!(1, socket.endpoints().len());
+        assert_eq!(
+            Err(Error::InvalidInput),
+            socket.shutdown_address("ipc:///tmp/socket_tracks_its_endpoints3.ipc")
+        );
+
+        socket.shutdown_all().unwrap();
+        assert!(socket.endpoints().is_empty());
+    }
+
+    #[test]
+    fn endpoint_guard_shuts_down_on_drop() {
+        let url = "ipc:///tmp/endpoint_guard_shuts_down_on_drop.ipc";
+        let mut socket = test_create_socket(Pair);
+
+        {
+            let guard = test_connect(&mut socket, url).into_guard();
+
+            assert_eq!(EndpointKind::Connect, guard.kind());
+            assert_eq!(1, socket.endpoints().len());
+        }
+
+        assert!(socket.endpoints().is_empty());
+
+        let endpoint = test_connect(&mut socket, url).into_guard().detach();
+
+        assert_eq!(
+            vec![endpoint.id()],
+            socket
+                .endpoints()
+                .iter()
+                .map(Endpoint::id)
+                .collect::<Vec<_>>()
+        );
+
+        let guard = socket.bind(url).unwrap().into_guard();
+
+        guard.shutdown().unwrap();
+        assert_eq!(1, socket.endpoints().len());
+    }
+
+    #[test]
+    fn endpoints_outliving_their_socket_are_not_shut_down() {
+        let url = "ipc:///tmp/endpoints_outliving_their_socket_are_not_shut_down.ipc";
+        let mut socket = test_create_socket(Pair);
+        let mut endpoint = test_connect(&mut socket, url);
+        let mut clone = endpoint.clone();
+
+        endpoint.shutdown().unwrap();
+        assert_eq!(Err(Error::InvalidInput), clone.shutdown());
+
+        let guard = test_connect(&mut socket, url).into_guard();
+        drop(socket);
+
+        // Another socket may reuse the id of the closed one.
+        let mut other = test_create_socket(Pair);
+        test_bind(&mut other, url);
+
+        assert_eq!(Err(Error::BadFileDescriptor), guard.shutdown());
+        assert_eq!(1, other.endpoints().len());
+    }
+
+    #[test]
+    fn reconcile_connects_and_shuts_down_endpoints() {
+        let addr = |n: u32| -> Address {
+            format!(
+                "ipc:///tmp/reconcile_connects_and_shuts_down_endpoints{}.ipc",
+                n
+            )
+            .parse()
+            .unwrap()
+        };
+        let mut socket = test_create_socket(Sub);
+        let bound = test_bind(&mut socket, "ipc:///tmp/reconcile_bound.ipc");
+
+        socket.reconcile(&[addr(1), addr(2)]).unwrap();
+        socket.reconcile(&[addr(2), addr(3)]).unwrap();
+
+        let mut addrs: Vec<Address> = socket
+            .endpoints()
+            .iter()
+            .map(|endpoint| endpoint.address().clone())
+            .collect();
+        addrs.sort_by_key(|addr| addr.to_string());
+
+        assert_eq!(vec![bound.address().clone(), addr(2), addr(3)], addrs);
+
+        let invalid = Address::Tcp {
+            interface: None,
+            host: String::new(),
+            port: 5555,
+        };
+
+        assert_eq!(
+            Err(Error::InvalidInput),
+            socket.reconcile(&[addr(4), invalid])
+        );
+        assert_eq!(3, socket.endpoints().len());
+    }
+
+    #[test]
+    fn statistics_count_messages_and_connections() {
+        let url = "ipc:///tmp/statistics_count_messages_and_connections.ipc";
+
+        let mut push_socket = test_create_socket(Push);
+        test_bind(&mut push_socket, url);
+
+        let mut pull_socket = test_create_socket(Pull);
+        test_connect(&mut pull_socket, url);
+
+        thread::sleep(Duration::from_millis(10));
+
+        let before = push_socket.statistics().unwrap();
+
+        test_write(&mut push_socket, b"foobar");
+        test_read(&mut pull_socket, b"foobar");
+
+        let delta = push_socket.statistics().unwrap().since(&before);
+        let pull_stats = pull_socket.statistics().unwrap();
+
+        assert_eq!(1, delta.messages_sent);
+        assert_eq!(6, delta.bytes_sent);
+        assert_eq!(1, delta.current_connections);
+        assert_eq!(1, pull_stats.established_connections);
+        assert_eq!(1, pull_stats.messages_received);
+    }
+
+    #[test]
+    fn bind_rejects_malformed_address() {
+        let mut socket = test_create_socket(Pull);
+
+        assert_eq!(
+            Err(Error::InvalidInput),
+            socket.bind("tcp://127.0.0.1:port").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::ProtocolNotSupported),
+            socket.connect("udp://127.0.0.1:5555").map(|_| ())
+        );
+    }
+
     fn test_create_socket(protocol: Protocol) -> Socket {
         match Socket::new(protocol) {
             Ok(socket) => socket,
@@ -1358,6 +2430,7 @@ mod tests {
         }
     }
 
+    #[allow(clippy::manual_memcpy)]
     fn test_zc_write(socket: &mut Socket, buf: &[u8]) {
         let msg = Socket::allocate_msg(buf.len()).unwrap();
         for i in 0..buf.len() {
@@ -1458,6 +2531,155 @@ mod tests {
         drop(push_socket);
     }
 
+    #[test]
+    fn send_msg_and_recv_msg_work() {
+        let url = "ipc:///tmp/send_msg_and_recv_msg_work.ipc";
+
+        let mut push_socket = test_create_socket(Push);
+        let mut push_endpoint = test_bind(&mut push_socket, url);
+
+        let mut pull_socket = test_create_socket(Pull);
+        test_connect(&mut pull_socket, url);
+
+        thread::sleep(Duration::from_millis(10));
+
+        let msg = Message::from_slice(b"foobar").unwrap();
+        match push_socket.send_msg(msg) {
+            Ok(len) => assert_eq!(6, len),
+            Err(err) => panic!("Failed to send message: {}", err),
+        }
+
+        match pull_socket.recv_msg() {
+            Ok(msg) => assert_eq!(b"foobar", &msg[..]),
+            Err(err) => panic!("Failed to receive message: {}", err),
+        }
+
+        push_endpoint.shutdown();
+
+        drop(pull_socket);
+        drop(push_socket);
+    }
+
+    #[test]
+    fn send_msg_does_not_send_spare_capacity() {
+        let url = "ipc:///tmp/send_msg_does_not_send_spare_capacity.ipc";
+
+        let mut push_socket = test_create_socket(Push);
+        test_bind(&mut push_socket, url);
+
+        let mut pull_socket = test_create_socket(Pull);
+        test_connect(&mut pull_socket, url);
+
+        thread::sleep(Duration::from_millis(10));
+
+        let mut msg = Message::with_capacity(1024).unwrap();
+        msg.extend_from_slice(b"foo").unwrap();
+        msg.extend_from_slice(b"bar").unwrap();
+        push_socket.send_msg(msg).unwrap();
+
+        match pull_socket.recv_msg() {
+            Ok(msg) => assert_eq!(b"foobar", &msg[..]),
+            Err(err) => panic!("Failed to receive message: {}", err),
+        }
+    }
+
+    #[test]
+    fn nb_recv_msg_works_in_both_cases() {
+        let url = "ipc:///tmp/nb_recv_msg_works_in_both_cases.ipc";
+
+        let mut push_socket = test_create_socket(Push);
+        test_bind(&mut push_socket, url);
+
+        let mut pull_socket = test_create_socket(Pull);
+        test_connect(&mut pull_socket, url);
+        thread::sleep(Duration::from_millis(10));
+
+        match pull_socket.nb_recv_msg() {
+            Ok(_) => panic!("Nothing should have been received !"),
+            Err(err) => assert_eq!(err, Error::TryAgain),
+        }
+
+        test_write(&mut push_socket, b"foobar");
+        thread::sleep(Duration::from_millis(10));
+
+        match pull_socket.nb_recv_msg() {
+            Ok(msg) => assert_eq!(b"foobar", &msg[..]),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn vectored_send_and_recv_work() {
+        let url = "ipc:///tmp/vectored_send_and_recv_work.ipc";
+
+        let mut push_socket = test_create_socket(Push);
+        test_bind(&mut push_socket, url);
+
+        let mut pull_socket = test_create_socket(Pull);
+        test_connect(&mut pull_socket, url);
+
+        thread::sleep(Duration::from_millis(10));
+
+        let sent = push_socket
+            .send_vectored(&[IoSlice::new(b"foo"), IoSlice::new(b"bar")])
+            .unwrap();
+        assert_eq!(6, sent);
+
+        let mut head = [0u8; 2];
+        let mut tail = [0u8; 8];
+        let received = pull_socket
+            .recv_vectored(&mut [IoSliceMut::new(&mut head), IoSliceMut::new(&mut tail)])
+            .unwrap();
+        assert_eq!(6, received);
+        assert_eq!(b"fo", &head);
+        assert_eq!(b"obar", &tail[..4]);
+    }
+
+    #[test]
+    fn write_vectored_sends_a_single_message() {
+        let url = "ipc:///tmp/write_vectored_sends_a_single_message.ipc";
+
+        let mut push_socket = test_create_socket(Push);
+        test_bind(&mut push_socket, url);
+
+        let mut pull_socket = test_create_socket(Pull);
+        test_connect(&mut pull_socket, url);
+
+        thread::sleep(Duration::from_millis(10));
+
+        let bufs = [IoSlice::new(b"foo"), IoSlice::new(b"bar")];
+        assert_eq!(6, push_socket.write_vectored(&bufs).unwrap());
+        test_read(&mut pull_socket, b"foobar");
+    }
+
+    #[test]
+    fn raw_rep_socket_can_route_reply_with_backtrace() {
+        let url = "ipc:///tmp/raw_rep_socket_can_route_reply_with_backtrace.ipc";
+
+        let mut server = Socket::new_for_device(Rep).unwrap();
+        test_bind(&mut server, url);
+
+        let mut client = test_create_socket(Req);
+        test_connect(&mut client, url);
+
+        thread::sleep(Duration::from_millis(10));
+
+        test_write(&mut client, b"foobar");
+
+        let request = server.recv_raw_msg().unwrap();
+        let backtrace = request.backtrace().unwrap();
+        assert_eq!(1, backtrace.hops().len());
+        assert_eq!(b"foobar", &request.body()[..]);
+
+        let reply = RawMessage::new(
+            backtrace.to_bytes(),
+            Message::from_slice(b"barfoo").unwrap(),
+        );
+        server.send_raw_msg(reply).unwrap();
+
+        test_read(&mut client, b"barfoo");
+    }
+
     fn test_multithread_pipeline(url: &'static str) {
         // this is required to prevent the sender from being dropped too early
         let finish_line = Arc::new(Barrier::new(3));
@@ -1520,6 +2742,7 @@ mod tests {
         drop(right_socket);
     }
 
+    #[allow(clippy::assign_op_pattern)]
     #[test]
     fn connect_push_to_multi_ep() {
         let url1 = "ipc:///tmp/connect_push_to_multi_ep_1.ipc";
@@ -1649,6 +2872,50 @@ mod tests {
         drop(sock1);
     }
 
+    #[test]
+    fn pubsub_over_ws() {
+        let url = "ws://127.0.0.1:5473";
+
+        let mut sock1 = test_create_socket(Pub);
+        test_bind(&mut sock1, url);
+
+        let mut sock2 = test_create_socket(Sub);
+        test_subscribe(&mut sock2, b"foo");
+        test_connect(&mut sock2, url);
+
+        thread::sleep(Duration::from_millis(150));
+
+        let msg = b"foobar";
+        test_write(&mut sock1, msg);
+        test_read(&mut sock2, msg);
+
+        drop(sock2);
+        drop(sock1);
+    }
+
+    #[test]
+    fn pair_over_ws_with_text_frames() {
+        let url = "ws://127.0.0.1:5474/text";
+
+        let mut server = test_create_socket(Pair);
+        server.set_ws_msg_type(WsMessageType::Text).unwrap();
+        test_bind(&mut server, url);
+
+        let mut client = test_create_socket(Pair);
+        client.set_ws_msg_type(WsMessageType::Text).unwrap();
+        test_connect(&mut client, url);
+
+        thread::sleep(Duration::from_millis(150));
+
+        test_write(&mut client, b"foobar");
+        test_read(&mut server, b"foobar");
+        test_write(&mut server, b"barfoo");
+        test_read(&mut client, b"barfoo");
+
+        drop(client);
+        drop(server);
+    }
+
     #[test]
     fn reqrep() {
         let url = "ipc:///tmp/reqrep.ipc";
@@ -1878,6 +3145,19 @@ mod tests {
         drop(socket)
     }
 
+    #[test]
+    fn should_change_ws_msg_type() {
+        let mut socket = test_create_socket(Pair);
+
+        assert_eq!(WsMessageType::Binary, socket.get_ws_msg_type().unwrap());
+
+        socket.set_ws_msg_type(WsMessageType::Text).unwrap();
+
+        assert_eq!(WsMessageType::Text, socket.get_ws_msg_type().unwrap());
+
+        drop(socket)
+    }
+
     #[test]
     fn should_get_receive_fd() {
         let mut socket = test_create_socket(Pair);
@@ -1914,6 +3194,159 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_get_socket_options_back() {
+        let mut socket = test_create_socket(Pair);
+
+        socket.set_linger(1024).unwrap();
+        socket.set_send_buffer_size(64 * 1024).unwrap();
+        socket.set_receive_buffer_size(32 * 1024).unwrap();
+        socket.set_receive_max_size(-1).unwrap();
+        socket.set_send_timeout(250).unwrap();
+        socket.set_receive_timeout(-1).unwrap();
+        socket.set_reconnect_interval(142).unwrap();
+        socket.set_max_reconnect_interval(666).unwrap();
+        socket.set_send_priority(15).unwrap();
+        socket.set_receive_priority(2).unwrap();
+        socket.set_ipv4_only(false).unwrap();
+        socket.set_max_ttl(4).unwrap();
+
+        assert_eq!(1024, socket.get_linger().unwrap());
+        assert_eq!(64 * 1024, socket.get_send_buffer_size().unwrap());
+        assert_eq!(32 * 1024, socket.get_receive_buffer_size().unwrap());
+        assert_eq!(-1, socket.get_receive_max_size().unwrap());
+        assert_eq!(250, socket.get_send_timeout().unwrap());
+        assert_eq!(-1, socket.get_receive_timeout().unwrap());
+        assert_eq!(142, socket.get_reconnect_interval().unwrap());
+        assert_eq!(666, socket.get_max_reconnect_interval().unwrap());
+        assert_eq!(15, socket.get_send_priority().unwrap());
+        assert_eq!(2, socket.get_receive_priority().unwrap());
+        assert!(!socket.get_ipv4_only().unwrap());
+        assert_eq!(4, socket.get_max_ttl().unwrap());
+    }
+
+    #[test]
+    fn should_get_duration_options_back() {
+        let mut socket = test_create_socket(Pair);
+
+        socket.set_linger_duration(None).unwrap();
+        socket
+            .set_send_timeout_duration(Some(Duration::from_millis(250)))
+            .unwrap();
+        socket.set_receive_timeout_duration(None).unwrap();
+        socket
+            .set_reconnect_interval_duration(Duration::from_millis(142))
+            .unwrap();
+        socket
+            .set_max_reconnect_interval_duration(Duration::from_secs(1))
+            .unwrap();
+
+        assert_eq!(None, socket.get_linger_duration().unwrap());
+        assert_eq!(
+            Some(Duration::from_millis(250)),
+            socket.get_send_timeout_duration().unwrap()
+        );
+        assert_eq!(None, socket.get_receive_timeout_duration().unwrap());
+        assert_eq!(
+            Duration::from_millis(142),
+            socket.get_reconnect_interval_duration().unwrap()
+        );
+        assert_eq!(
+            Duration::from_secs(1),
+            socket.get_max_reconnect_interval_duration().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_get_protocol_duration_options_back() {
+        let mut surveyor = test_create_socket(Surveyor);
+        surveyor
+            .set_survey_deadline_duration(Duration::from_millis(500))
+            .unwrap();
+        assert_eq!(
+            Duration::from_millis(500),
+            surveyor.get_survey_deadline_duration().unwrap()
+        );
+
+        let mut requester = test_create_socket(Req);
+        requester
+            .set_request_resend_interval_duration(Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(
+            Duration::from_secs(60),
+            requester.get_request_resend_interval_duration().unwrap()
+        );
+    }
+
+    #[test]
+    fn duration_overflow_is_rejected() {
+        let mut socket = test_create_socket(Pair);
+        let too_long = Duration::from_secs(u64::MAX);
+
+        assert_eq!(
+            Err(Error::InvalidInput),
+            socket.set_send_timeout_duration(Some(too_long))
+        );
+    }
+
+    #[test]
+    fn negative_intervals_are_not_read_as_durations() {
+        let mut surveyor = test_create_socket(Surveyor);
+        surveyor.set_survey_deadline(-1).unwrap();
+        assert_eq!(
+            Err(Error::InvalidInput),
+            surveyor.get_survey_deadline_duration()
+        );
+
+        let mut requester = test_create_socket(Req);
+        requester.set_request_resend_interval(-1).unwrap();
+        assert_eq!(
+            Err(Error::InvalidInput),
+            requester.get_request_resend_interval_duration()
+        );
+
+        assert_eq!(Err(Error::InvalidInput), millis_to_interval(-1));
+        assert_eq!(Ok(Duration::from_millis(10)), millis_to_interval(10));
+    }
+
+    #[test]
+    fn duration_is_converted_to_millis() {
+        assert_eq!(Ok(-1), duration_to_millis(None));
+        assert_eq!(Ok(0), duration_to_millis(Some(Duration::from_millis(0))));
+        assert_eq!(Ok(1), duration_to_millis(Some(Duration::from_micros(1))));
+        assert_eq!(
+            Ok(1500),
+            duration_to_millis(Some(Duration::from_millis(1500)))
+        );
+        assert_eq!(
+            Err(Error::InvalidInput),
+            duration_to_millis(Some(Duration::from_millis(c_int::MAX as u64 + 1)))
+        );
+        assert_eq!(None, millis_to_duration(-1));
+        assert_eq!(Some(Duration::from_millis(10)), millis_to_duration(10));
+    }
+
+    #[test]
+    fn should_get_domain_and_protocol() {
+        let socket = test_create_socket(Surveyor);
+        assert_eq!(Domain::Sp, socket.get_domain().unwrap());
+        assert_eq!(Surveyor, socket.get_protocol().unwrap());
+
+        let raw_socket = Socket::new_for_device(Rep).unwrap();
+        assert_eq!(Domain::SpRaw, raw_socket.get_domain().unwrap());
+        assert_eq!(Rep, raw_socket.get_protocol().unwrap());
+    }
+
+    #[test]
+    fn protocol_can_be_converted_from_raw() {
+        for protocol in &[
+            Req, Rep, Push, Pull, Pair, Bus, Pub, Sub, Surveyor, Respondent,
+        ] {
+            assert_eq!(Some(*protocol), Protocol::from_raw(protocol.to_raw()));
+        }
+        assert_eq!(None, Protocol::from_raw(-1));
+    }
+
     #[test]
     fn protocol_matches_raw() {
         assert_eq!(nanomsg_sys::NN_REQ, Req.to_raw());
@@ -1928,6 +3361,7 @@ mod tests {
         assert_eq!(nanomsg_sys::NN_RESPONDENT, Respondent.to_raw());
     }
 
+    #[allow(clippy::useless_asref)]
     #[test]
     fn test_read_to_end() {
         let url = "ipc:///tmp/read_to_end.ipc";
@@ -2034,6 +3468,7 @@ mod tests {
         drop(push_socket);
     }
 
+    #[allow(clippy::bool_assert_comparison)]
     #[test]
     fn poll_works() {
         let url = "ipc:///tmp/poll_works_.ipc";
@@ -2086,4 +3521,30 @@ mod tests {
             assert_eq!(false, fds[1].can_read());
         }
     }
+
+    #[test]
+    fn poll_request_accepts_a_duration() {
+        let url = "ipc:///tmp/poll_request_accepts_a_duration.ipc";
+
+        let mut left_socket = test_create_socket(Pair);
+        test_bind(&mut left_socket, url);
+
+        let mut right_socket = test_create_socket(Pair);
+        test_connect(&mut right_socket, url);
+
+        thread::sleep(Duration::from_millis(10));
+
+        let mut pollfds = [left_socket.new_pollfd(PollInOut::In)];
+        let mut request = PollRequest::new(&mut pollfds[..]);
+
+        assert_eq!(
+            Err(Error::TimedOut),
+            request.poll_duration(Some(Duration::from_millis(10)))
+        );
+
+        test_write(&mut right_socket, b"foobar");
+
+        assert_eq!(Ok(1), request.poll_duration(None));
+        assert!(request.get_fds()[0].can_read());
+    }
 }
@@ -0,0 +1,361 @@
+use libc::{c_void, size_t};
+
+use std::cmp;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice;
+
+use crate::result::{last_nano_error, Error, Result};
+
+/// A message buffer allocated and owned by nanomsg.
+/// The buffer is released with `nn_freemsg` when the message is dropped,
+/// unless ownership was transferred back to nanomsg by `Socket::send_msg`.
+/// Messages received with `Socket::recv_msg` are returned as is, without any copy.
+///
+/// Like a `Vec<u8>`, a message has a length and a capacity.
+/// It can grow with `reserve`, `resize`, `extend_from_slice` or through its `io::Write` implementation,
+/// the underlying buffer being reallocated in place by `nn_reallocmsg` when needed.
+pub struct Message {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+}
+
+unsafe impl Send for Message {}
+unsafe impl Sync for Message {}
+
+impl Message {
+    /// Allocates a zero-filled message of the specified size to be sent in zero-copy fashion.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nanomsg::Message;
+    ///
+    /// let mut msg = Message::with_size(6).unwrap();
+    /// msg.copy_from_slice(b"foobar");
+    ///
+    /// assert_eq!(&msg[..], b"foobar");
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn with_size(len: usize) -> Result<Message> {
+        let mut msg = Message::with_capacity(len)?;
+
+        unsafe { ptr::write_bytes(msg.ptr, 0, len) };
+        msg.len = len;
+        Ok(msg)
+    }
+
+    /// Allocates an empty message able to hold `capacity` bytes without being reallocated.
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn with_capacity(capacity: usize) -> Result<Message> {
+        let ptr = unsafe { nanomsg_sys::nn_allocmsg(capacity as size_t, 0) as *mut u8 };
+
+        if ptr.is_null() {
+            return Err(last_nano_error());
+        }
+
+        Ok(Message {
+            ptr,
+            len: 0,
+            cap: capacity,
+        })
+    }
+
+    /// Allocates a message and copies the specified bytes into it.
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn from_slice(bytes: &[u8]) -> Result<Message> {
+        let mut msg = Message::with_capacity(bytes.len())?;
+
+        msg.extend_from_slice(bytes)?;
+        Ok(msg)
+    }
+
+    /// Returns the number of bytes the message can hold without being reallocated.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    /// The buffer may be given more space to avoid frequent reallocations.
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn reserve(&mut self, additional: usize) -> Result<()> {
+        let required = self.required_capacity(additional)?;
+
+        if required <= self.cap {
+            return Ok(());
+        }
+
+        let doubled = self.cap.saturating_mul(2);
+        self.reallocate(cmp::max(doubled, required))
+    }
+
+    /// Reserves capacity for exactly `additional` more bytes.
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn reserve_exact(&mut self, additional: usize) -> Result<()> {
+        let required = self.required_capacity(additional)?;
+
+        if required <= self.cap {
+            return Ok(());
+        }
+
+        self.reallocate(required)
+    }
+
+    /// Shrinks the capacity of the message to its length.
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn shrink_to_fit(&mut self) -> Result<()> {
+        if self.cap == self.len {
+            return Ok(());
+        }
+
+        self.reallocate(self.len)
+    }
+
+    /// Resizes the message in place so that its length is equal to `new_len`.
+    /// When growing, the new bytes are filled with `value`.
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn resize(&mut self, new_len: usize, value: u8) -> Result<()> {
+        if new_len <= self.len {
+            self.truncate(new_len);
+            return Ok(());
+        }
+
+        let additional = new_len - self.len;
+        self.reserve(additional)?;
+        unsafe { ptr::write_bytes(self.ptr.add(self.len), value, additional) };
+        self.len = new_len;
+        Ok(())
+    }
+
+    /// Shortens the message to `len` bytes, keeping its capacity.
+    /// Has no effect if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = cmp::min(self.len, len);
+    }
+
+    /// Removes all the bytes of the message, keeping its capacity.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Appends the specified bytes at the end of the message, growing it if needed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nanomsg::Message;
+    ///
+    /// let mut msg = Message::with_capacity(3).unwrap();
+    /// msg.extend_from_slice(b"foo").unwrap();
+    /// msg.extend_from_slice(b"bar").unwrap();
+    ///
+    /// assert_eq!(&msg[..], b"foobar");
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Unknown` : Out of memory.
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<()> {
+        self.reserve(bytes.len())?;
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), self.ptr.add(self.len), bytes.len()) };
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    fn required_capacity(&self, additional: usize) -> Result<usize> {
+        self.len
+            .checked_add(additional)
+            .ok_or_else(|| Error::from_raw(nanomsg_sys::EINVAL))
+    }
+
+    fn reallocate(&mut self, capacity: usize) -> Result<()> {
+        let ptr = unsafe {
+            nanomsg_sys::nn_reallocmsg(self.ptr as *mut c_void, capacity as size_t) as *mut u8
+        };
+
+        if ptr.is_null() {
+            return Err(last_nano_error());
+        }
+
+        self.ptr = ptr;
+        self.cap = capacity;
+        Ok(())
+    }
+
+    /// Takes ownership of a buffer allocated by nanomsg, such as the one returned by `nn_recv` with `NN_MSG`.
+    pub(crate) unsafe fn from_raw(ptr: *mut u8, len: usize) -> Message {
+        Message { ptr, len, cap: len }
+    }
+
+    /// Releases ownership of the buffer, the caller becomes responsible for freeing it.
+    pub(crate) fn into_raw(self) -> (*mut u8, usize) {
+        let raw = (self.ptr, self.len);
+
+        mem::forget(self);
+        raw
+    }
+}
+
+impl io::Write for Message {
+    /// Appends the bytes at the end of the message, growing it if needed.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Deref for Message {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for Message {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl AsRef<[u8]> for Message {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for Message {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl fmt::Debug for Message {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_tuple("Message").field(&&self[..]).finish()
+    }
+}
+
+impl Drop for Message {
+    /// Deallocates the message buffer.
+    fn drop(&mut self) {
+        unsafe { nanomsg_sys::nn_freemsg(self.ptr as *mut c_void) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Message;
+    use std::io::Write;
+
+    #[test]
+    fn with_size_allocates_requested_len() {
+        let msg = Message::with_size(10).unwrap();
+
+        assert_eq!(10, msg.len());
+    }
+
+    #[test]
+    fn from_slice_copies_bytes() {
+        let msg = Message::from_slice(b"foobar").unwrap();
+
+        assert_eq!(b"foobar", &msg[..]);
+    }
+
+    #[test]
+    fn message_can_be_modified_in_place() {
+        let mut msg = Message::from_slice(b"foobar").unwrap();
+
+        msg[3..].copy_from_slice(b"baz");
+
+        assert_eq!(b"foobaz", &msg[..]);
+    }
+
+    #[test]
+    fn empty_message_can_be_allocated() {
+        let msg = Message::with_size(0).unwrap();
+
+        assert!(msg.is_empty());
+    }
+
+    #[test]
+    fn extend_from_slice_grows_message() {
+        let mut msg = Message::with_capacity(2).unwrap();
+
+        msg.extend_from_slice(b"foo").unwrap();
+        msg.extend_from_slice(b"bar").unwrap();
+
+        assert_eq!(b"foobar", &msg[..]);
+        assert!(msg.capacity() >= 6);
+    }
+
+    #[test]
+    fn reserve_keeps_content() {
+        let mut msg = Message::from_slice(b"foobar").unwrap();
+
+        msg.reserve(1024).unwrap();
+
+        assert_eq!(b"foobar", &msg[..]);
+        assert!(msg.capacity() >= 1030);
+    }
+
+    #[test]
+    fn resize_can_grow_and_shrink() {
+        let mut msg = Message::from_slice(b"foo").unwrap();
+
+        msg.resize(5, b'!').unwrap();
+        assert_eq!(b"foo!!", &msg[..]);
+
+        msg.resize(2, b'?').unwrap();
+        assert_eq!(b"fo", &msg[..]);
+    }
+
+    #[test]
+    fn shrink_to_fit_releases_spare_capacity() {
+        let mut msg = Message::with_capacity(64).unwrap();
+
+        msg.extend_from_slice(b"foobar").unwrap();
+        msg.shrink_to_fit().unwrap();
+
+        assert_eq!(6, msg.capacity());
+        assert_eq!(b"foobar", &msg[..]);
+    }
+
+    #[test]
+    fn message_can_be_written_to() {
+        let mut msg = Message::with_capacity(0).unwrap();
+
+        write!(msg, "foo-{}", 42).unwrap();
+
+        assert_eq!(b"foo-42", &msg[..]);
+    }
+}
@@ -0,0 +1,333 @@
+//! OpenMetrics exposition of socket statistics.
+//!
+//! A `MetricsCollector` keeps track of named sockets and renders their statistics
+//! in the OpenMetrics text format, ready to be served to Prometheus by any HTTP server.
+
+use libc::c_int;
+
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+use crate::endpoint::EndpointRegistry;
+use crate::result::Result;
+use crate::statistics::{self, Statistics};
+use crate::Socket;
+
+/// Maximum length of a socket name, including the terminating null byte.
+const SOCKET_NAME_MAX: usize = 64;
+
+enum MetricType {
+    Counter,
+    Gauge,
+}
+
+struct Metric {
+    name: &'static str,
+    help: &'static str,
+    metric_type: MetricType,
+    value: fn(&Statistics) -> u64,
+}
+
+const METRICS: [Metric; 15] = [
+    Metric {
+        name: "nanomsg_established_connections",
+        help: "Number of connections established from the socket.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.established_connections,
+    },
+    Metric {
+        name: "nanomsg_accepted_connections",
+        help: "Number of connections accepted by the socket.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.accepted_connections,
+    },
+    Metric {
+        name: "nanomsg_dropped_connections",
+        help: "Number of established connections dropped by the socket.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.dropped_connections,
+    },
+    Metric {
+        name: "nanomsg_broken_connections",
+        help: "Number of established connections closed by the peer.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.broken_connections,
+    },
+    Metric {
+        name: "nanomsg_connect_errors",
+        help: "Number of errors while connecting to remote endpoints.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.connect_errors,
+    },
+    Metric {
+        name: "nanomsg_bind_errors",
+        help: "Number of errors while binding to local addresses.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.bind_errors,
+    },
+    Metric {
+        name: "nanomsg_accept_errors",
+        help: "Number of errors while accepting connections.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.accept_errors,
+    },
+    Metric {
+        name: "nanomsg_current_connections",
+        help: "Number of connections currently established.",
+        metric_type: MetricType::Gauge,
+        value: |stats| stats.current_connections,
+    },
+    Metric {
+        name: "nanomsg_inprogress_connections",
+        help: "Number of connections currently in progress.",
+        metric_type: MetricType::Gauge,
+        value: |stats| stats.inprogress_connections,
+    },
+    Metric {
+        name: "nanomsg_current_endpoint_errors",
+        help: "Number of endpoints currently in an error state.",
+        metric_type: MetricType::Gauge,
+        value: |stats| stats.current_endpoint_errors,
+    },
+    Metric {
+        name: "nanomsg_messages_sent",
+        help: "Number of messages sent.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.messages_sent,
+    },
+    Metric {
+        name: "nanomsg_messages_received",
+        help: "Number of messages received.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.messages_received,
+    },
+    Metric {
+        name: "nanomsg_bytes_sent",
+        help: "Number of bytes sent.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.bytes_sent,
+    },
+    Metric {
+        name: "nanomsg_bytes_received",
+        help: "Number of bytes received.",
+        metric_type: MetricType::Counter,
+        value: |stats| stats.bytes_received,
+    },
+    Metric {
+        name: "nanomsg_current_send_priority",
+        help: "Current send priority of the socket.",
+        metric_type: MetricType::Gauge,
+        value: |stats| stats.current_send_priority,
+    },
+];
+
+struct RegisteredSocket {
+    name: String,
+    socket: c_int,
+    // The endpoint registry is closed and dropped with the socket,
+    // telling whether the socket id still refers to the registered socket.
+    registry: Weak<EndpointRegistry>,
+}
+
+/// Collects the statistics of registered sockets and renders them in the OpenMetrics text format.
+/// Each socket is labelled with its name, see `Socket::set_socket_name`.
+/// Sockets are automatically unregistered once they are dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use nanomsg::{MetricsCollector, Protocol, Socket};
+///
+/// let mut socket = Socket::new(Protocol::Pub).unwrap();
+/// socket.set_socket_name("feed").unwrap();
+///
+/// let collector = MetricsCollector::new();
+/// collector.register(&socket).unwrap();
+///
+/// let text = collector.render();
+/// assert!(text.contains("nanomsg_messages_sent_total{socket=\"feed\"} 0\n"));
+/// ```
+#[derive(Default)]
+pub struct MetricsCollector {
+    sockets: Mutex<Vec<RegisteredSocket>>,
+}
+
+impl MetricsCollector {
+    /// Creates a collector with no socket.
+    pub fn new() -> MetricsCollector {
+        MetricsCollector::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<RegisteredSocket>> {
+        self.sockets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Registers the socket under its current name.
+    /// The name should be set beforehand, otherwise nanomsg's default `socket.N` is used.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    pub fn register(&self, socket: &Socket) -> Result<()> {
+        let name = socket
+            .get_socket_option_str(
+                nanomsg_sys::NN_SOL_SOCKET,
+                nanomsg_sys::NN_SOCKET_NAME,
+                SOCKET_NAME_MAX,
+            )?
+            .to_string_lossy()
+            .into_owned();
+
+        self.unregister(socket);
+        self.lock().push(RegisteredSocket {
+            name,
+            socket: socket.socket,
+            registry: Arc::downgrade(&socket.endpoints),
+        });
+        Ok(())
+    }
+
+    /// Stops collecting the statistics of the socket.
+    pub fn unregister(&self, socket: &Socket) {
+        self.lock()
+            .retain(|registered| registered.socket != socket.socket);
+    }
+
+    /// Returns the names of the registered sockets, in registration order.
+    pub fn names(&self) -> Vec<String> {
+        self.lock()
+            .iter()
+            .filter(|registered| registered.registry.strong_count() > 0)
+            .map(|registered| registered.name.clone())
+            .collect()
+    }
+
+    /// Reads the statistics of all the live registered sockets and renders them.
+    /// Counters are exposed with the `_total` suffix, each sample is labelled with `socket="<name>"`.
+    /// The text ends with the `# EOF` marker.
+    pub fn render(&self) -> String {
+        let mut sockets = self.lock();
+
+        sockets.retain(|registered| registered.registry.strong_count() > 0);
+
+        let snapshots: Vec<(&str, Statistics)> = sockets
+            .iter()
+            .filter_map(|registered| {
+                let registry = registered.registry.upgrade()?;
+                // Keeps the socket open while its statistics are read.
+                let _open = registry.lock_open()?;
+
+                statistics::read(registered.socket)
+                    .ok()
+                    .map(|stats| (registered.name.as_str(), stats))
+            })
+            .collect();
+
+        let mut text = String::new();
+
+        for metric in METRICS.iter() {
+            let (type_name, suffix) = match metric.metric_type {
+                MetricType::Counter => ("counter", "_total"),
+                MetricType::Gauge => ("gauge", ""),
+            };
+
+            let _ = writeln!(text, "# TYPE {} {}", metric.name, type_name);
+            let _ = writeln!(text, "# HELP {} {}", metric.name, metric.help);
+
+            for &(name, ref stats) in &snapshots {
+                let _ = writeln!(
+                    text,
+                    "{}{}{{socket=\"{}\"}} {}",
+                    metric.name,
+                    suffix,
+                    escape_label_value(name),
+                    (metric.value)(stats)
+                );
+            }
+        }
+
+        text.push_str("# EOF\n");
+        text
+    }
+}
+
+/// Escapes a label value as required by the OpenMetrics text format.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_label_value, MetricsCollector};
+    use crate::{Protocol, Socket};
+
+    use std::io::{Read, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!("a\\\"b\\\\c\\nd", escape_label_value("a\"b\\c\nd"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn collector_renders_named_sockets() {
+        let url = "ipc:///tmp/collector_renders_named_sockets.ipc";
+        let collector = MetricsCollector::new();
+
+        let mut push_socket = Socket::new(Protocol::Push).unwrap();
+        push_socket.set_socket_name("pusher").unwrap();
+        push_socket.bind(url).unwrap();
+        collector.register(&push_socket).unwrap();
+
+        let mut pull_socket = Socket::new(Protocol::Pull).unwrap();
+        pull_socket.set_socket_name("puller").unwrap();
+        pull_socket.connect(url).unwrap();
+        collector.register(&pull_socket).unwrap();
+
+        thread::sleep(Duration::from_millis(10));
+
+        push_socket.write_all(b"foobar").unwrap();
+        let mut buf = [0u8; 6];
+        pull_socket.read_exact(&mut buf).unwrap();
+
+        let text = collector.render();
+
+        assert!(text.starts_with("# TYPE nanomsg_established_connections counter\n"));
+        assert!(text.contains("# TYPE nanomsg_current_connections gauge\n"));
+        assert!(text.contains("nanomsg_messages_sent_total{socket=\"pusher\"} 1\n"));
+        assert!(text.contains("nanomsg_bytes_received_total{socket=\"puller\"} 6\n"));
+        assert!(text.contains("nanomsg_current_connections{socket=\"puller\"} 1\n"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn dropped_sockets_are_unregistered() {
+        let collector = MetricsCollector::new();
+        let mut socket = Socket::new(Protocol::Pair).unwrap();
+
+        socket.set_socket_name("dropped").unwrap();
+        collector.register(&socket).unwrap();
+        assert_eq!(vec!["dropped".to_owned()], collector.names());
+
+        drop(socket);
+
+        assert!(collector.names().is_empty());
+        assert!(!collector.render().contains("dropped"));
+    }
+}
@@ -0,0 +1,256 @@
+use libc::size_t;
+use nanomsg_sys::{nn_cmsghdr, NN_CMSG_DATA, NN_CMSG_SPACE, PROTO_SP, SP_HDR};
+
+use std::mem::size_of;
+use std::ptr;
+use std::slice;
+
+use crate::message::Message;
+use crate::result::{Error, Result};
+
+/// A message received from, or to be sent to, a raw socket created with `Socket::new_for_device`.
+/// Besides the body, raw sockets carry the SP header that protocols such as `Req`/`Rep`
+/// and `Surveyor`/`Respondent` use to route replies back to the original requester.
+///
+/// **See also:** `Socket::recv_raw_msg`, `Socket::send_raw_msg` and `Backtrace`.
+#[derive(Debug)]
+pub struct RawMessage {
+    header: Vec<u8>,
+    body: Message,
+}
+
+impl RawMessage {
+    /// Creates a message from its SP header and its body.
+    pub fn new(header: Vec<u8>, body: Message) -> RawMessage {
+        RawMessage { header, body }
+    }
+
+    /// Returns the SP header of the message.
+    pub fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    /// Returns the SP header of the message so it can be modified.
+    pub fn header_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.header
+    }
+
+    /// Returns the body of the message.
+    pub fn body(&self) -> &Message {
+        &self.body
+    }
+
+    /// Returns the body of the message so it can be modified.
+    pub fn body_mut(&mut self) -> &mut Message {
+        &mut self.body
+    }
+
+    /// Parses the SP header as a request-id stack, see `Backtrace::parse`.
+    pub fn backtrace(&self) -> Result<Backtrace> {
+        Backtrace::parse(&self.header)
+    }
+
+    /// Replaces the SP header with the serialized request-id stack.
+    pub fn set_backtrace(&mut self, backtrace: &Backtrace) {
+        self.header = backtrace.to_bytes();
+    }
+
+    /// Splits the message into its SP header and its body.
+    pub fn into_parts(self) -> (Vec<u8>, Message) {
+        (self.header, self.body)
+    }
+}
+
+/// The request-id stack found in the SP header of `Req`/`Rep` and `Surveyor`/`Respondent` messages.
+///
+/// The header is a sequence of 32-bit big-endian words.
+/// Each device the message went through pushes the id of the pipe the message was received from,
+/// so replies can be routed back. The last word is the id of the request (or survey),
+/// it is the only one with the most significant bit set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backtrace {
+    hops: Vec<u32>,
+    request_id: u32,
+}
+
+const REQUEST_ID_FLAG: u32 = 0x8000_0000;
+
+impl Backtrace {
+    /// Creates a backtrace made only of the specified request id.
+    /// The most significant bit of the request id is ignored.
+    pub fn new(request_id: u32) -> Backtrace {
+        Backtrace {
+            hops: Vec::new(),
+            request_id: request_id & !REQUEST_ID_FLAG,
+        }
+    }
+
+    /// Parses an SP header.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nanomsg::Backtrace;
+    ///
+    /// let header = [0, 0, 0, 7, 0x80, 0, 0, 42];
+    /// let backtrace = Backtrace::parse(&header).unwrap();
+    ///
+    /// assert_eq!(backtrace.hops(), &[7]);
+    /// assert_eq!(backtrace.request_id(), 42);
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `InvalidInput` : The header is not a sequence of words terminated by a request id.
+    pub fn parse(header: &[u8]) -> Result<Backtrace> {
+        let mut hops = Vec::new();
+
+        for word in header.chunks(4) {
+            if word.len() != 4 {
+                break;
+            }
+
+            let value = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+
+            if value & REQUEST_ID_FLAG != 0 {
+                let request_id = value & !REQUEST_ID_FLAG;
+                return Ok(Backtrace { hops, request_id });
+            }
+
+            hops.push(value);
+        }
+
+        Err(Error::InvalidInput)
+    }
+
+    /// Returns the pipe ids of the stack, the most recent hop first.
+    pub fn hops(&self) -> &[u32] {
+        &self.hops
+    }
+
+    /// Returns the id of the request or survey, without its most significant bit.
+    pub fn request_id(&self) -> u32 {
+        self.request_id
+    }
+
+    /// Pushes a hop on top of the stack.
+    /// The most significant bit of the pipe id is ignored.
+    pub fn push_hop(&mut self, pipe_id: u32) {
+        self.hops.insert(0, pipe_id & !REQUEST_ID_FLAG);
+    }
+
+    /// Removes the most recent hop from the stack and returns it.
+    pub fn pop_hop(&mut self) -> Option<u32> {
+        if self.hops.is_empty() {
+            None
+        } else {
+            Some(self.hops.remove(0))
+        }
+    }
+
+    /// Serializes the stack into an SP header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity((self.hops.len() + 1) * 4);
+
+        for hop in &self.hops {
+            header.extend_from_slice(&hop.to_be_bytes());
+        }
+
+        header.extend_from_slice(&(self.request_id | REQUEST_ID_FLAG).to_be_bytes());
+        header
+    }
+}
+
+/// Extracts the SP header from the control part of a message received with `nn_recvmsg`.
+/// nanomsg always stores the `SP_HDR` property first, its payload being the size of the header followed by the header.
+pub(crate) unsafe fn read_sp_header(control: *const u8) -> Vec<u8> {
+    if control.is_null() {
+        return Vec::new();
+    }
+
+    let cmsg = control as *const nn_cmsghdr;
+    let cmsg_hdr = ptr::read_unaligned(cmsg);
+
+    if cmsg_hdr.cmsg_level != PROTO_SP || cmsg_hdr.cmsg_type != SP_HDR {
+        return Vec::new();
+    }
+
+    let data = NN_CMSG_DATA(cmsg);
+    let len = ptr::read_unaligned(data as *const size_t);
+    let bytes = slice::from_raw_parts(data.add(size_of::<size_t>()), len);
+
+    bytes.to_vec()
+}
+
+/// Builds the control part of a message to be sent with `nn_sendmsg`, made of a single `SP_HDR` property.
+/// The buffer is made of `size_t` so the property header is properly aligned.
+pub(crate) fn write_sp_header(header: &[u8]) -> Vec<size_t> {
+    let space = NN_CMSG_SPACE(size_of::<size_t>() + header.len());
+    let mut control = vec![0 as size_t; space / size_of::<size_t>()];
+    let cmsg = nn_cmsghdr {
+        cmsg_len: space,
+        cmsg_level: PROTO_SP,
+        cmsg_type: SP_HDR,
+    };
+
+    unsafe {
+        let base = control.as_mut_ptr() as *mut u8;
+        let data = NN_CMSG_DATA(base as *const nn_cmsghdr) as *mut u8;
+
+        ptr::write(base as *mut nn_cmsghdr, cmsg);
+        ptr::write_unaligned(data as *mut size_t, header.len());
+        ptr::copy_nonoverlapping(header.as_ptr(), data.add(size_of::<size_t>()), header.len());
+    }
+
+    control
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_sp_header, write_sp_header, Backtrace};
+    use crate::Error;
+
+    #[test]
+    fn backtrace_can_be_parsed() {
+        let header = [0, 0, 0, 1, 0, 0, 0, 2, 0x80, 0, 0, 3];
+        let backtrace = Backtrace::parse(&header).unwrap();
+
+        assert_eq!(&[1, 2], backtrace.hops());
+        assert_eq!(3, backtrace.request_id());
+    }
+
+    #[test]
+    fn backtrace_without_request_id_is_rejected() {
+        assert_eq!(Err(Error::InvalidInput), Backtrace::parse(&[0, 0, 0, 1]));
+        assert_eq!(Err(Error::InvalidInput), Backtrace::parse(&[0x80, 0, 0]));
+        assert_eq!(Err(Error::InvalidInput), Backtrace::parse(&[]));
+    }
+
+    #[test]
+    fn backtrace_round_trips_through_bytes() {
+        let mut backtrace = Backtrace::new(42);
+
+        backtrace.push_hop(7);
+        backtrace.push_hop(9);
+
+        assert_eq!(
+            vec![0, 0, 0, 9, 0, 0, 0, 7, 0x80, 0, 0, 42],
+            backtrace.to_bytes()
+        );
+        assert_eq!(
+            Ok(backtrace.clone()),
+            Backtrace::parse(&backtrace.to_bytes())
+        );
+        assert_eq!(Some(9), backtrace.pop_hop());
+        assert_eq!(&[7], backtrace.hops());
+    }
+
+    #[test]
+    fn sp_header_round_trips_through_control() {
+        let header = [0, 0, 0, 9, 0x80, 0, 0, 42];
+        let control = write_sp_header(&header);
+        let read = unsafe { read_sp_header(control.as_ptr() as *const u8) };
+
+        assert_eq!(&header[..], &read[..]);
+    }
+}
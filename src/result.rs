@@ -2,6 +2,7 @@ extern crate libc;
 
 use libc::c_int;
 
+use std::convert;
 use std::convert::From;
 use std::error;
 use std::ffi::CStr;
@@ -13,6 +14,7 @@ use std::str;
 pub type Result<T> = result::Result<T, Error>;
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[allow(clippy::unnecessary_cast)]
 pub enum Error {
     Unknown = 0 as isize,
     OperationNotSupported = nanomsg_sys::ENOTSUP as isize,
@@ -51,6 +53,9 @@ pub enum Error {
     NoDevice = nanomsg_sys::ENODEV as isize,
     FileStateMismatch = nanomsg_sys::EFSM as isize,
     Interrupted = nanomsg_sys::EINTR as isize,
+    /// The feature is not available in the linked nanomsg library, see `Feature`.
+    /// Unlike the other variants, it does not map to an error code of the library.
+    Unsupported = -1,
 }
 
 impl Error {
@@ -103,6 +108,12 @@ impl Error {
 
 impl error::Error for Error {}
 
+impl From<convert::Infallible> for Error {
+    fn from(err: convert::Infallible) -> Error {
+        match err {}
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
         match err.kind() {
@@ -124,6 +135,7 @@ impl From<io::Error> for Error {
 }
 
 impl From<Error> for io::Error {
+    #[allow(clippy::io_other_error)]
     fn from(err: Error) -> io::Error {
         let as_std_error: &dyn error::Error = &err;
         let description = as_std_error.to_string();
@@ -146,6 +158,7 @@ impl From<Error> for io::Error {
             Error::InvalidInput => io::Error::new(io::ErrorKind::InvalidInput, description),
             Error::TimedOut => io::Error::new(io::ErrorKind::TimedOut, description),
             Error::Interrupted => io::Error::new(io::ErrorKind::Interrupted, description),
+            Error::Unsupported => io::Error::new(io::ErrorKind::Unsupported, description),
             _ => io::Error::new(io::ErrorKind::Other, description),
         }
     }
@@ -153,6 +166,10 @@ impl From<Error> for io::Error {
 
 impl fmt::Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if *self == Error::Unsupported {
+            return write!(formatter, "Not supported by the linked nanomsg library");
+        }
+
         let description = unsafe {
             let nn_errno = *self as c_int;
             let c_ptr: *const libc::c_char = nanomsg_sys::nn_strerror(nn_errno);
@@ -209,6 +226,7 @@ mod tests {
         check_error_kind_match(Error::OperationNotSupported, io::ErrorKind::Other);
         check_error_kind_match(Error::NotConnected, io::ErrorKind::NotConnected);
         check_error_kind_match(Error::Interrupted, io::ErrorKind::Interrupted);
+        check_error_kind_match(Error::Unsupported, io::ErrorKind::Unsupported);
     }
 
     #[test]
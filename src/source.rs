@@ -0,0 +1,245 @@
+//! Integration with the `mio` event loop, see the `mio` feature.
+//!
+//! nanomsg sockets are not file descriptors, but they expose two descriptors signalling readiness:
+//! `NN_RCVFD` becomes readable when a message can be received,
+//! and `NN_SNDFD` becomes readable when a message can be sent.
+
+use mio::event::Source;
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token};
+
+use std::io;
+use std::os::unix::io::RawFd;
+
+use crate::Socket;
+
+/// Returns the descriptors of the socket matching the interests.
+fn interest_fds(socket: &mut Socket, interests: Interest) -> io::Result<Vec<RawFd>> {
+    let mut fds = Vec::with_capacity(2);
+
+    if interests.is_readable() {
+        fds.push(socket.get_receive_fd()?);
+    }
+    if interests.is_writable() {
+        fds.push(socket.get_send_fd()?);
+    }
+
+    Ok(fds)
+}
+
+/// Returns the descriptors available for the protocol of the socket,
+/// a `Push` socket for example has no receive descriptor.
+fn available_fds(socket: &mut Socket) -> Vec<RawFd> {
+    let receive_fd = socket.get_receive_fd().ok();
+    let send_fd = socket.get_send_fd().ok();
+
+    receive_fd.into_iter().chain(send_fd).collect()
+}
+
+/// Registers the receive descriptor for `Interest::READABLE` and the send descriptor for `Interest::WRITABLE`.
+/// Both descriptors share the token, so an event does not tell which operation is ready when both are registered:
+/// handle it by trying both `Socket::nb_read` and `Socket::nb_write`, treating `Error::TryAgain` as a spurious wakeup.
+///
+/// As the `mio` notifications are edge-triggered, the socket must then be drained,
+/// by calling `nb_read` until it fails with `Error::TryAgain`, before waiting for the next event.
+///
+/// # Example
+///
+/// ```rust
+/// use mio::{Events, Interest, Poll, Token};
+/// use nanomsg::{Error, Protocol, Socket};
+///
+/// let mut socket = Socket::new(Protocol::Pull).unwrap();
+/// socket.bind("ipc:///tmp/mio_source_doc.ipc").unwrap();
+///
+/// let mut poll = Poll::new().unwrap();
+/// poll.registry().register(&mut socket, Token(0), Interest::READABLE).unwrap();
+///
+/// let mut events = Events::with_capacity(8);
+/// poll.poll(&mut events, Some(std::time::Duration::from_millis(10))).unwrap();
+///
+/// for _ in events.iter() {
+///     let mut buf = [0u8; 1024];
+///     loop {
+///         match socket.nb_read(&mut buf) {
+///             Ok(len) => println!("received {} bytes", len),
+///             Err(Error::TryAgain) => break,
+///             Err(err) => panic!("{}", err),
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Error
+///
+/// - `ProtocolNotAvailable` : The protocol of the socket cannot receive or send, as requested by the interests.
+impl Source for Socket {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        for fd in interest_fds(self, interests)? {
+            SourceFd(&fd).register(registry, token, Interest::READABLE)?;
+        }
+
+        Ok(())
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        let fds = interest_fds(self, interests)?;
+
+        // The interests may switch from one descriptor to the other.
+        self.deregister(registry)?;
+        for fd in fds {
+            SourceFd(&fd).register(registry, token, Interest::READABLE)?;
+        }
+
+        Ok(())
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        for fd in available_fds(self) {
+            // Only the descriptors matching the interests were registered.
+            if let Err(err) = SourceFd(&fd).deregister(registry) {
+                if err.kind() != io::ErrorKind::NotFound {
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Protocol, Socket};
+
+    use mio::{Events, Interest, Poll, Token};
+
+    use std::thread;
+    use std::time::Duration;
+
+    const TOKEN: Token = Token(7);
+
+    fn wait(poll: &mut Poll, millis: u64) -> Vec<Token> {
+        let mut events = Events::with_capacity(8);
+
+        poll.poll(&mut events, Some(Duration::from_millis(millis)))
+            .unwrap();
+        events.iter().map(|event| event.token()).collect()
+    }
+
+    fn drain(socket: &Socket) -> usize {
+        let mut buf = [0u8; 16];
+        let mut count = 0;
+
+        loop {
+            match socket.nb_read(&mut buf) {
+                Ok(_) => count += 1,
+                Err(Error::TryAgain) => return count,
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn readiness_is_edge_triggered() {
+        let url = "ipc:///tmp/mio_readiness_is_edge_triggered.ipc";
+        let mut pull_socket = Socket::new(Protocol::Pull).unwrap();
+        let mut push_socket = Socket::new(Protocol::Push).unwrap();
+        let mut poll = Poll::new().unwrap();
+
+        pull_socket.bind(url).unwrap();
+        push_socket.connect(url).unwrap();
+        poll.registry()
+            .register(&mut pull_socket, TOKEN, Interest::READABLE)
+            .unwrap();
+
+        assert!(wait(&mut poll, 10).is_empty());
+
+        push_socket.nb_write(b"foo").unwrap();
+        push_socket.nb_write(b"bar").unwrap();
+        thread::sleep(Duration::from_millis(10));
+
+        assert_eq!(vec![TOKEN], wait(&mut poll, 1000));
+        assert_eq!(2, drain(&pull_socket));
+
+        // The readiness was consumed, only a new message triggers another event.
+        assert!(wait(&mut poll, 10).is_empty());
+
+        push_socket.nb_write(b"baz").unwrap();
+
+        assert_eq!(vec![TOKEN], wait(&mut poll, 1000));
+        assert_eq!(1, drain(&pull_socket));
+
+        poll.registry().deregister(&mut pull_socket).unwrap();
+    }
+
+    #[test]
+    fn spurious_wakeups_are_reported_as_try_again() {
+        let url = "ipc:///tmp/mio_spurious_wakeups_are_reported_as_try_again.ipc";
+        let mut left_socket = Socket::new(Protocol::Pair).unwrap();
+        let mut right_socket = Socket::new(Protocol::Pair).unwrap();
+        let mut poll = Poll::new().unwrap();
+
+        left_socket.bind(url).unwrap();
+        right_socket.connect(url).unwrap();
+        thread::sleep(Duration::from_millis(10));
+
+        poll.registry()
+            .register(
+                &mut left_socket,
+                TOKEN,
+                Interest::READABLE | Interest::WRITABLE,
+            )
+            .unwrap();
+
+        // The event comes from the send descriptor, there is nothing to receive.
+        assert_eq!(vec![TOKEN], wait(&mut poll, 1000));
+
+        let mut buf = [0u8; 16];
+        assert_eq!(Err(Error::TryAgain), left_socket.nb_read(&mut buf));
+        assert_eq!(Ok(6), left_socket.nb_write(b"foobar"));
+    }
+
+    #[test]
+    fn interests_can_switch_descriptors() {
+        let url = "ipc:///tmp/mio_interests_can_switch_descriptors.ipc";
+        let mut left_socket = Socket::new(Protocol::Pair).unwrap();
+        let mut right_socket = Socket::new(Protocol::Pair).unwrap();
+        let mut poll = Poll::new().unwrap();
+
+        left_socket.bind(url).unwrap();
+        right_socket.connect(url).unwrap();
+        thread::sleep(Duration::from_millis(10));
+
+        poll.registry()
+            .register(&mut left_socket, TOKEN, Interest::READABLE)
+            .unwrap();
+        assert!(wait(&mut poll, 10).is_empty());
+
+        poll.registry()
+            .reregister(&mut left_socket, TOKEN, Interest::WRITABLE)
+            .unwrap();
+        assert_eq!(vec![TOKEN], wait(&mut poll, 1000));
+    }
+
+    #[test]
+    fn unavailable_direction_cannot_be_registered() {
+        let mut push_socket = Socket::new(Protocol::Push).unwrap();
+        let poll = Poll::new().unwrap();
+
+        assert!(poll
+            .registry()
+            .register(&mut push_socket, TOKEN, Interest::READABLE)
+            .is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Socket statistics, as reported by `nn_get_statistic`.
+
+use libc::c_int;
+
+#[cfg(any(nanomsg_0_5, not(nanomsg_version)))]
+use crate::result::last_nano_error;
+#[cfg(not(any(nanomsg_0_5, not(nanomsg_version))))]
+use crate::result::Error;
+use crate::result::Result;
+use crate::version::{self, Feature};
+
+/// A snapshot of the statistics of a socket, taken by `Socket::statistics`.
+///
+/// Most values are counters, increasing since the creation of the socket.
+/// `current_connections`, `inprogress_connections`, `current_endpoint_errors`
+/// and `current_send_priority` are gauges, reflecting the state at the time of the snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    /// Number of connections successfully established that were initiated from this socket.
+    pub established_connections: u64,
+    /// Number of connections accepted by this socket.
+    pub accepted_connections: u64,
+    /// Number of established connections dropped by this socket.
+    pub dropped_connections: u64,
+    /// Number of established connections closed by the peer.
+    pub broken_connections: u64,
+    /// Number of errors encountered while trying to connect to a remote endpoint.
+    pub connect_errors: u64,
+    /// Number of errors encountered while trying to bind to a local address.
+    pub bind_errors: u64,
+    /// Number of errors encountered while trying to accept a connection.
+    pub accept_errors: u64,
+    /// Number of connections currently established to this socket.
+    pub current_connections: u64,
+    /// Number of connections currently in progress.
+    pub inprogress_connections: u64,
+    /// Number of endpoints currently in an error state.
+    pub current_endpoint_errors: u64,
+    /// Number of messages sent.
+    pub messages_sent: u64,
+    /// Number of messages received.
+    pub messages_received: u64,
+    /// Number of bytes sent, SP headers excluded.
+    pub bytes_sent: u64,
+    /// Number of bytes received, SP headers excluded.
+    pub bytes_received: u64,
+    /// The current send priority of the socket.
+    pub current_send_priority: u64,
+}
+
+impl Statistics {
+    /// Returns the change since an earlier snapshot of the same socket:
+    /// counters hold the difference between the two snapshots, gauges keep the value of `self`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nanomsg::Statistics;
+    ///
+    /// let earlier = Statistics { messages_sent: 3, current_connections: 2, ..Default::default() };
+    /// let later = Statistics { messages_sent: 10, current_connections: 1, ..Default::default() };
+    /// let delta = later.since(&earlier);
+    ///
+    /// assert_eq!(7, delta.messages_sent);
+    /// assert_eq!(1, delta.current_connections);
+    /// ```
+    pub fn since(&self, earlier: &Statistics) -> Statistics {
+        Statistics {
+            established_connections: self
+                .established_connections
+                .saturating_sub(earlier.established_connections),
+            accepted_connections: self
+                .accepted_connections
+                .saturating_sub(earlier.accepted_connections),
+            dropped_connections: self
+                .dropped_connections
+                .saturating_sub(earlier.dropped_connections),
+            broken_connections: self
+                .broken_connections
+                .saturating_sub(earlier.broken_connections),
+            connect_errors: self.connect_errors.saturating_sub(earlier.connect_errors),
+            bind_errors: self.bind_errors.saturating_sub(earlier.bind_errors),
+            accept_errors: self.accept_errors.saturating_sub(earlier.accept_errors),
+            current_connections: self.current_connections,
+            inprogress_connections: self.inprogress_connections,
+            current_endpoint_errors: self.current_endpoint_errors,
+            messages_sent: self.messages_sent.saturating_sub(earlier.messages_sent),
+            messages_received: self
+                .messages_received
+                .saturating_sub(earlier.messages_received),
+            bytes_sent: self.bytes_sent.saturating_sub(earlier.bytes_sent),
+            bytes_received: self.bytes_received.saturating_sub(earlier.bytes_received),
+            current_send_priority: self.current_send_priority,
+        }
+    }
+}
+
+#[cfg(any(nanomsg_0_5, not(nanomsg_version)))]
+fn get_statistic(socket: c_int, stat: c_int) -> Result<u64> {
+    let value = unsafe { nanomsg_sys::nn_get_statistic(socket, stat) };
+
+    if value == u64::MAX {
+        Err(last_nano_error())
+    } else {
+        Ok(value)
+    }
+}
+
+/// `nn_get_statistic` is missing from the linked library.
+#[cfg(not(any(nanomsg_0_5, not(nanomsg_version))))]
+fn get_statistic(_socket: c_int, _stat: c_int) -> Result<u64> {
+    Err(Error::Unsupported)
+}
+
+/// Reads all the statistics of the socket.
+pub(crate) fn read(socket: c_int) -> Result<Statistics> {
+    version::require(Feature::Statistics)?;
+
+    Ok(Statistics {
+        established_connections: get_statistic(
+            socket,
+            nanomsg_sys::NN_STAT_ESTABLISHED_CONNECTIONS,
+        )?,
+        accepted_connections: get_statistic(socket, nanomsg_sys::NN_STAT_ACCEPTED_CONNECTIONS)?,
+        dropped_connections: get_statistic(socket, nanomsg_sys::NN_STAT_DROPPED_CONNECTIONS)?,
+        broken_connections: get_statistic(socket, nanomsg_sys::NN_STAT_BROKEN_CONNECTIONS)?,
+        connect_errors: get_statistic(socket, nanomsg_sys::NN_STAT_CONNECT_ERRORS)?,
+        bind_errors: get_statistic(socket, nanomsg_sys::NN_STAT_BIND_ERRORS)?,
+        accept_errors: get_statistic(socket, nanomsg_sys::NN_STAT_ACCEPT_ERRORS)?,
+        current_connections: get_statistic(socket, nanomsg_sys::NN_STAT_CURRENT_CONNECTIONS)?,
+        inprogress_connections: get_statistic(socket, nanomsg_sys::NN_STAT_INPROGRESS_CONNECTIONS)?,
+        current_endpoint_errors: get_statistic(socket, nanomsg_sys::NN_STAT_CURRENT_EP_ERRORS)?,
+        messages_sent: get_statistic(socket, nanomsg_sys::NN_STAT_MESSAGES_SENT)?,
+        messages_received: get_statistic(socket, nanomsg_sys::NN_STAT_MESSAGES_RECEIVED)?,
+        bytes_sent: get_statistic(socket, nanomsg_sys::NN_STAT_BYTES_SENT)?,
+        bytes_received: get_statistic(socket, nanomsg_sys::NN_STAT_BYTES_RECEIVED)?,
+        current_send_priority: get_statistic(socket, nanomsg_sys::NN_STAT_CURRENT_SND_PRIORITY)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Statistics;
+
+    #[test]
+    fn since_subtracts_counters_and_keeps_gauges() {
+        let earlier = Statistics {
+            accepted_connections: 1,
+            bytes_received: 100,
+            current_connections: 1,
+            current_send_priority: 8,
+            ..Default::default()
+        };
+        let later = Statistics {
+            accepted_connections: 3,
+            bytes_received: 160,
+            current_connections: 2,
+            current_send_priority: 4,
+            ..Default::default()
+        };
+        let delta = later.since(&earlier);
+
+        assert_eq!(2, delta.accepted_connections);
+        assert_eq!(60, delta.bytes_received);
+        assert_eq!(2, delta.current_connections);
+        assert_eq!(4, delta.current_send_priority);
+    }
+
+    #[test]
+    fn since_never_underflows() {
+        let earlier = Statistics {
+            messages_sent: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(0, Statistics::default().since(&earlier).messages_sent);
+    }
+}
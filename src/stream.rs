@@ -0,0 +1,278 @@
+//! `Stream` and `Sink` adapters over typed sockets, see the `tokio_stream` feature.
+//!
+//! The adapters implement the traits of the `futures` crate, so that any combinator built on them can be used.
+//! Readiness is still reported by the tokio reactor, see `AsyncSocket`.
+
+use futures_core::{FusedStream, Stream};
+use futures_sink::Sink;
+
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use crate::result::{Error, Result};
+use crate::{AsyncSocket, Message, RecvSocket, SendSocket, Socket};
+
+/// The messages of a typed socket, as a `Stream` when its protocol can receive (`Pull`, `Sub`, `Rep`,
+/// `Respondent`, `Pair`, `Bus`, `Surveyor`) and as a `Sink` when it can send.
+///
+/// Errors, such as `TimedOut` once a survey deadline is reached, are yielded as items.
+/// The stream only ends after yielding `Terminating` or `BadFileDescriptor`, the socket being then unusable.
+/// The sink buffers a single message, `poll_ready` waits for it to be sent and `start_send` fails with `TryAgain` until then.
+///
+/// Although the traits come from the `futures` crate, readiness is reported by the tokio reactor:
+/// `Messages::new` must be called within a tokio runtime, and the adapters polled on it.
+///
+/// # Example
+///
+/// ```rust
+/// use futures::{SinkExt, StreamExt};
+/// use nanomsg::{Message, Messages, PullSocket, PushSocket};
+///
+/// # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+/// let mut pull_socket = PullSocket::new().unwrap();
+/// pull_socket.bind("ipc:///tmp/messages_doc.ipc").unwrap();
+/// let mut push_socket = PushSocket::new().unwrap();
+/// push_socket.connect("ipc:///tmp/messages_doc.ipc").unwrap();
+///
+/// let mut incoming = Messages::new(pull_socket).unwrap();
+/// let mut outgoing = Messages::new(push_socket).unwrap();
+///
+/// outgoing.send(Message::from_slice(b"foobar").unwrap()).await.unwrap();
+///
+/// let msg = incoming.next().await.unwrap().unwrap();
+/// assert_eq!(b"foobar", &msg[..]);
+/// # });
+/// ```
+pub struct Messages<S> {
+    socket: AsyncSocket,
+    pending: Option<Message>,
+    terminated: bool,
+    protocol: PhantomData<fn() -> S>,
+}
+
+impl<S: Into<Socket>> Messages<S> {
+    /// Registers the typed socket in the reactor of the current tokio runtime, see `AsyncSocket::new`.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime, or if the runtime has no IO driver enabled.
+    ///
+    /// # Error
+    ///
+    /// - `BadFileDescriptor` : The socket is invalid.
+    pub fn new(socket: S) -> Result<Messages<S>> {
+        AsyncSocket::new(socket).map(|socket| Messages {
+            socket,
+            pending: None,
+            terminated: false,
+            protocol: PhantomData,
+        })
+    }
+
+    /// Returns the underlying socket.
+    pub fn get_ref(&self) -> &Socket {
+        self.socket.get_ref()
+    }
+
+    /// Returns the underlying socket, to bind, connect or set options.
+    pub fn get_mut(&mut self) -> &mut Socket {
+        self.socket.get_mut()
+    }
+}
+
+impl<S: RecvSocket> Stream for Messages<S> {
+    type Item = Result<Message>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Message>>> {
+        let this = self.get_mut();
+
+        if this.terminated {
+            return Poll::Ready(None);
+        }
+
+        let received = ready!(this.socket.poll_recv(cx));
+
+        if let Err(Error::Terminating) | Err(Error::BadFileDescriptor) = received {
+            this.terminated = true;
+        }
+
+        Poll::Ready(Some(received))
+    }
+}
+
+impl<S: RecvSocket> FusedStream for Messages<S> {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl<S: SendSocket> Sink<Message> for Messages<S> {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.poll_flush(cx)
+    }
+
+    /// Buffers the message, it is sent by `poll_flush`.
+    ///
+    /// # Error
+    ///
+    /// - `TryAgain` : A message is still buffered, `poll_ready` was not awaited. The message is not buffered.
+    fn start_send(self: Pin<&mut Self>, msg: Message) -> Result<()> {
+        let this = self.get_mut();
+
+        if this.pending.is_some() {
+            return Err(Error::TryAgain);
+        }
+
+        this.pending = Some(msg);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+
+        // Without a buffered message there is nothing to wait for, the socket may not even be connected.
+        if this.pending.is_none() {
+            return Poll::Ready(Ok(()));
+        }
+
+        this.socket.poll_send(cx, &mut this.pending).map_ok(|_| ())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Messages;
+    use crate::{Error, Message, PairSocket, PubSocket, PullSocket, PushSocket, Result, SubSocket};
+
+    use futures::{stream, SinkExt, StreamExt};
+
+    use std::thread;
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    fn msg(bytes: &[u8]) -> Message {
+        Message::from_slice(bytes).unwrap()
+    }
+
+    fn push_pull(url: &str) -> (Messages<PushSocket>, Messages<PullSocket>) {
+        let mut push_socket = PushSocket::new().unwrap();
+        let mut pull_socket = PullSocket::new().unwrap();
+
+        pull_socket.bind(url).unwrap();
+        push_socket.connect(url).unwrap();
+
+        (
+            Messages::new(push_socket).unwrap(),
+            Messages::new(pull_socket).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn sink_and_stream_exchange_messages() {
+        let (mut outgoing, mut incoming) =
+            push_pull("ipc:///tmp/sink_and_stream_exchange_messages.ipc");
+
+        outgoing.send(msg(b"foo")).await.unwrap();
+        outgoing.send(msg(b"bar")).await.unwrap();
+
+        let received: Vec<Vec<u8>> = incoming
+            .by_ref()
+            .take(2)
+            .map(|msg| msg.unwrap().to_vec())
+            .collect()
+            .await;
+
+        assert_eq!(vec![b"foo".to_vec(), b"bar".to_vec()], received);
+    }
+
+    #[tokio::test]
+    async fn stream_can_be_forwarded_to_the_sink() {
+        let (mut outgoing, incoming) =
+            push_pull("ipc:///tmp/stream_can_be_forwarded_to_the_sink.ipc");
+        let items: Vec<Result<Message>> = vec![Ok(msg(b"one")), Ok(msg(b"two")), Ok(msg(b"three"))];
+
+        stream::iter(items).forward(&mut outgoing).await.unwrap();
+
+        let received: Vec<Vec<u8>> = incoming
+            .take(3)
+            .map(|msg| msg.unwrap().to_vec())
+            .collect()
+            .await;
+
+        assert_eq!(3, received.len());
+        assert_eq!(b"three".to_vec(), received[2]);
+    }
+
+    #[tokio::test]
+    async fn stream_can_be_selected_with_other_streams() {
+        let url = "ipc:///tmp/stream_can_be_selected_with_other_streams.ipc";
+        let mut pub_socket = PubSocket::new().unwrap();
+        let mut sub_socket = SubSocket::new().unwrap();
+
+        pub_socket.bind(url).unwrap();
+        sub_socket.subscribe(b"").unwrap();
+        sub_socket.connect(url).unwrap();
+        thread::sleep(Duration::from_millis(10));
+
+        let mut outgoing = Messages::new(pub_socket).unwrap();
+        let incoming = Messages::new(sub_socket)
+            .unwrap()
+            .map(|msg| msg.unwrap().to_vec());
+        let ticks = stream::iter(vec![b"tick".to_vec()]);
+
+        outgoing.send(msg(b"news")).await.unwrap();
+
+        let mut received: Vec<Vec<u8>> = stream::select(incoming, ticks).take(2).collect().await;
+        received.sort();
+
+        assert_eq!(vec![b"news".to_vec(), b"tick".to_vec()], received);
+    }
+
+    #[tokio::test]
+    async fn bidirectional_sockets_can_be_split() {
+        let url = "ipc:///tmp/bidirectional_sockets_can_be_split.ipc";
+        let mut left_socket = PairSocket::new().unwrap();
+        let mut right_socket = PairSocket::new().unwrap();
+
+        left_socket.bind(url).unwrap();
+        right_socket.connect(url).unwrap();
+
+        let (mut left_sink, _left_stream) = Messages::new(left_socket).unwrap().split();
+        let (_right_sink, mut right_stream) = Messages::new(right_socket).unwrap().split();
+
+        left_sink.send(msg(b"ping")).await.unwrap();
+
+        let received = right_stream.next().await.unwrap().unwrap();
+        assert_eq!(b"ping", &received[..]);
+    }
+
+    #[tokio::test]
+    async fn unconnected_sink_can_be_closed() {
+        let mut outgoing = Messages::new(PushSocket::new().unwrap()).unwrap();
+
+        timeout(Duration::from_secs(1), outgoing.close())
+            .await
+            .expect("closing a sink without pending message must not wait for the socket")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn start_send_rejects_a_second_message_before_flushing() {
+        let mut outgoing = Messages::new(PushSocket::new().unwrap()).unwrap();
+
+        outgoing.start_send_unpin(msg(b"first")).unwrap();
+
+        assert_eq!(
+            Err(Error::TryAgain),
+            outgoing.start_send_unpin(msg(b"second"))
+        );
+    }
+}
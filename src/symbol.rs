@@ -0,0 +1,434 @@
+//! Introspection of the symbols exposed by the linked nanomsg library,
+//! as reported by `nn_symbol_info`.
+
+use libc::c_int;
+
+use std::ffi::CStr;
+use std::iter::FusedIterator;
+use std::mem;
+
+/// The category a symbol belongs to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SymbolNamespace {
+    /// The namespaces themselves, `NN_NS_*`.
+    Namespace = (nanomsg_sys::NN_NS_NAMESPACE) as isize,
+    /// Version of the library, `NN_VERSION_*`.
+    Version = (nanomsg_sys::NN_NS_VERSION) as isize,
+    /// Socket domains, `AF_SP` and `AF_SP_RAW`.
+    Domain = (nanomsg_sys::NN_NS_DOMAIN) as isize,
+    /// Transports, `NN_INPROC`, `NN_IPC`, ...
+    Transport = (nanomsg_sys::NN_NS_TRANSPORT) as isize,
+    /// Protocols, `NN_PAIR`, `NN_PUB`, ...
+    Protocol = (nanomsg_sys::NN_NS_PROTOCOL) as isize,
+    /// Option levels, `NN_SOL_SOCKET`.
+    OptionLevel = (nanomsg_sys::NN_NS_OPTION_LEVEL) as isize,
+    /// Generic socket options, `NN_LINGER`, `NN_SNDBUF`, ...
+    SocketOption = (nanomsg_sys::NN_NS_SOCKET_OPTION) as isize,
+    /// Protocol and transport specific options, `NN_SUB_SUBSCRIBE`, `NN_TCP_NODELAY`, ...
+    TransportOption = (nanomsg_sys::NN_NS_TRANSPORT_OPTION) as isize,
+    /// Option types, `NN_TYPE_*`.
+    OptionType = (nanomsg_sys::NN_NS_OPTION_TYPE) as isize,
+    /// Option units, `NN_UNIT_*`.
+    OptionUnit = (nanomsg_sys::NN_NS_OPTION_UNIT) as isize,
+    /// Send and receive flags, `NN_DONTWAIT`, ...
+    Flag = (nanomsg_sys::NN_NS_FLAG) as isize,
+    /// Error codes, `EINVAL`, `ETERM`, ...
+    Error = (nanomsg_sys::NN_NS_ERROR) as isize,
+    /// Limits, `NN_SOCKADDR_MAX`.
+    Limit = (nanomsg_sys::NN_NS_LIMIT) as isize,
+    /// Poll events, `NN_POLLIN` and `NN_POLLOUT`.
+    Event = (nanomsg_sys::NN_NS_EVENT) as isize,
+    /// Socket statistics, `NN_STAT_*`.
+    Statistic = (nanomsg_sys::NN_NS_STATISTIC) as isize,
+    /// A namespace introduced by a newer version of the library.
+    Unknown = -1,
+}
+
+impl SymbolNamespace {
+    pub fn from_raw(raw: c_int) -> SymbolNamespace {
+        match raw {
+            nanomsg_sys::NN_NS_NAMESPACE => SymbolNamespace::Namespace,
+            nanomsg_sys::NN_NS_VERSION => SymbolNamespace::Version,
+            nanomsg_sys::NN_NS_DOMAIN => SymbolNamespace::Domain,
+            nanomsg_sys::NN_NS_TRANSPORT => SymbolNamespace::Transport,
+            nanomsg_sys::NN_NS_PROTOCOL => SymbolNamespace::Protocol,
+            nanomsg_sys::NN_NS_OPTION_LEVEL => SymbolNamespace::OptionLevel,
+            nanomsg_sys::NN_NS_SOCKET_OPTION => SymbolNamespace::SocketOption,
+            nanomsg_sys::NN_NS_TRANSPORT_OPTION => SymbolNamespace::TransportOption,
+            nanomsg_sys::NN_NS_OPTION_TYPE => SymbolNamespace::OptionType,
+            nanomsg_sys::NN_NS_OPTION_UNIT => SymbolNamespace::OptionUnit,
+            nanomsg_sys::NN_NS_FLAG => SymbolNamespace::Flag,
+            nanomsg_sys::NN_NS_ERROR => SymbolNamespace::Error,
+            nanomsg_sys::NN_NS_LIMIT => SymbolNamespace::Limit,
+            nanomsg_sys::NN_NS_EVENT => SymbolNamespace::Event,
+            nanomsg_sys::NN_NS_STATISTIC => SymbolNamespace::Statistic,
+            _ => SymbolNamespace::Unknown,
+        }
+    }
+}
+
+/// The type of the value of an option symbol.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SymbolType {
+    /// The symbol is not an option.
+    None = (nanomsg_sys::NN_TYPE_NONE) as isize,
+    /// The option value is an integer.
+    Int = (nanomsg_sys::NN_TYPE_INT) as isize,
+    /// The option value is a string.
+    Str = (nanomsg_sys::NN_TYPE_STR) as isize,
+    /// A type introduced by a newer version of the library.
+    Unknown = -1,
+}
+
+impl SymbolType {
+    pub fn from_raw(raw: c_int) -> SymbolType {
+        match raw {
+            nanomsg_sys::NN_TYPE_NONE => SymbolType::None,
+            nanomsg_sys::NN_TYPE_INT => SymbolType::Int,
+            nanomsg_sys::NN_TYPE_STR => SymbolType::Str,
+            _ => SymbolType::Unknown,
+        }
+    }
+}
+
+/// The unit of the value of an option or statistic symbol.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SymbolUnit {
+    /// The value has no unit.
+    None = (nanomsg_sys::NN_UNIT_NONE) as isize,
+    /// A size, in bytes.
+    Bytes = (nanomsg_sys::NN_UNIT_BYTES) as isize,
+    /// A duration, in milliseconds.
+    Milliseconds = (nanomsg_sys::NN_UNIT_MILLISECONDS) as isize,
+    /// A priority, from 1 (highest) to 16 (lowest).
+    Priority = (nanomsg_sys::NN_UNIT_PRIORITY) as isize,
+    /// Zero is false, any other value is true.
+    Boolean = (nanomsg_sys::NN_UNIT_BOOLEAN) as isize,
+    /// A number of messages.
+    Messages = (nanomsg_sys::NN_UNIT_MESSAGES) as isize,
+    /// A number of occurrences of an event, such as the statistics of dropped connections.
+    Counter = (nanomsg_sys::NN_UNIT_COUNTER) as isize,
+    /// A unit introduced by a newer version of the library.
+    Unknown = -1,
+}
+
+impl SymbolUnit {
+    pub fn from_raw(raw: c_int) -> SymbolUnit {
+        match raw {
+            nanomsg_sys::NN_UNIT_NONE => SymbolUnit::None,
+            nanomsg_sys::NN_UNIT_BYTES => SymbolUnit::Bytes,
+            nanomsg_sys::NN_UNIT_MILLISECONDS => SymbolUnit::Milliseconds,
+            nanomsg_sys::NN_UNIT_PRIORITY => SymbolUnit::Priority,
+            nanomsg_sys::NN_UNIT_BOOLEAN => SymbolUnit::Boolean,
+            nanomsg_sys::NN_UNIT_MESSAGES => SymbolUnit::Messages,
+            nanomsg_sys::NN_UNIT_COUNTER => SymbolUnit::Counter,
+            _ => SymbolUnit::Unknown,
+        }
+    }
+}
+
+/// A named constant exposed by the linked library.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Symbol {
+    /// The name of the constant, as written in the C headers.
+    pub name: &'static str,
+    /// The value of the constant.
+    pub value: c_int,
+    /// The category of the constant.
+    pub namespace: SymbolNamespace,
+    /// The type of the option value, `SymbolType::None` for symbols that are not options.
+    pub value_type: SymbolType,
+    /// The unit of the option or statistic value, `SymbolUnit::None` for other symbols.
+    pub unit: SymbolUnit,
+}
+
+/// Iterator over the symbols of the linked library, created by `symbols`.
+#[derive(Debug, Clone)]
+pub struct Symbols {
+    index: c_int,
+}
+
+impl Iterator for Symbols {
+    type Item = Symbol;
+
+    fn next(&mut self) -> Option<Symbol> {
+        let mut props: nanomsg_sys::nn_symbol_properties = unsafe { mem::zeroed() };
+        let len = mem::size_of::<nanomsg_sys::nn_symbol_properties>() as c_int;
+
+        loop {
+            let written = symbol_info(self.index, &mut props, len);
+
+            if written == 0 || props.name.is_null() {
+                return None;
+            }
+
+            self.index += 1;
+
+            // Names are static ASCII literals, anything else is skipped rather than truncated.
+            let name = unsafe { CStr::from_ptr(props.name) };
+
+            if let Ok(name) = name.to_str() {
+                return Some(Symbol {
+                    name,
+                    value: props.value,
+                    namespace: SymbolNamespace::from_raw(props.ns),
+                    value_type: SymbolType::from_raw(props.type_),
+                    unit: SymbolUnit::from_raw(props.unit),
+                });
+            }
+        }
+    }
+}
+
+impl FusedIterator for Symbols {}
+
+#[cfg(any(nanomsg_0_4, not(nanomsg_version)))]
+fn symbol_info(index: c_int, props: &mut nanomsg_sys::nn_symbol_properties, len: c_int) -> c_int {
+    unsafe { nanomsg_sys::nn_symbol_info(index, props, len) }
+}
+
+/// `nn_symbol_info` is missing from the linked library, which then exposes no symbol.
+#[cfg(not(any(nanomsg_0_4, not(nanomsg_version))))]
+fn symbol_info(
+    _index: c_int,
+    _props: &mut nanomsg_sys::nn_symbol_properties,
+    _len: c_int,
+) -> c_int {
+    0
+}
+
+/// Returns an iterator over all the symbols exposed by the linked library,
+/// in the order the library reports them.
+///
+/// # Example
+///
+/// ```rust
+/// use nanomsg::SymbolNamespace;
+///
+/// let protocols: Vec<&str> = nanomsg::symbols()
+///     .filter(|symbol| symbol.namespace == SymbolNamespace::Protocol)
+///     .map(|symbol| symbol.name)
+///     .collect();
+///
+/// assert!(protocols.contains(&"NN_PUB"));
+/// ```
+pub fn symbols() -> Symbols {
+    Symbols { index: 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{symbols, Symbol, SymbolNamespace, SymbolType, SymbolUnit};
+    use crate::version::Feature;
+
+    use libc::c_int;
+    use std::collections::HashMap;
+
+    /// The constants hardcoded in `nanomsg_sys` that the library exposes, under their C names.
+    fn exported_constants() -> Vec<(&'static str, c_int)> {
+        use nanomsg_sys as sys;
+
+        vec![
+            ("AF_SP", sys::AF_SP),
+            ("AF_SP_RAW", sys::AF_SP_RAW),
+            ("NN_INPROC", sys::NN_INPROC),
+            ("NN_IPC", sys::NN_IPC),
+            ("NN_TCP", sys::NN_TCP),
+            ("NN_WS", sys::NN_WS),
+            ("NN_PAIR", sys::NN_PAIR),
+            ("NN_PUB", sys::NN_PUB),
+            ("NN_SUB", sys::NN_SUB),
+            ("NN_REP", sys::NN_REP),
+            ("NN_REQ", sys::NN_REQ),
+            ("NN_PUSH", sys::NN_PUSH),
+            ("NN_PULL", sys::NN_PULL),
+            ("NN_SURVEYOR", sys::NN_SURVEYOR),
+            ("NN_RESPONDENT", sys::NN_RESPONDENT),
+            ("NN_BUS", sys::NN_BUS),
+            ("NN_SOCKADDR_MAX", sys::NN_SOCKADDR_MAX),
+            ("NN_SOL_SOCKET", sys::NN_SOL_SOCKET),
+            ("NN_LINGER", sys::NN_LINGER),
+            ("NN_SNDBUF", sys::NN_SNDBUF),
+            ("NN_RCVBUF", sys::NN_RCVBUF),
+            ("NN_RCVMAXSIZE", sys::NN_RCVMAXSIZE),
+            ("NN_SNDTIMEO", sys::NN_SNDTIMEO),
+            ("NN_RCVTIMEO", sys::NN_RCVTIMEO),
+            ("NN_RECONNECT_IVL", sys::NN_RECONNECT_IVL),
+            ("NN_RECONNECT_IVL_MAX", sys::NN_RECONNECT_IVL_MAX),
+            ("NN_SNDPRIO", sys::NN_SNDPRIO),
+            ("NN_RCVPRIO", sys::NN_RCVPRIO),
+            ("NN_SNDFD", sys::NN_SNDFD),
+            ("NN_RCVFD", sys::NN_RCVFD),
+            ("NN_DOMAIN", sys::NN_DOMAIN),
+            ("NN_PROTOCOL", sys::NN_PROTOCOL),
+            ("NN_IPV4ONLY", sys::NN_IPV4ONLY),
+            ("NN_SOCKET_NAME", sys::NN_SOCKET_NAME),
+            ("NN_MAXTTL", sys::NN_MAXTTL),
+            ("NN_SUB_SUBSCRIBE", sys::NN_SUB_SUBSCRIBE),
+            ("NN_SUB_UNSUBSCRIBE", sys::NN_SUB_UNSUBSCRIBE),
+            ("NN_REQ_RESEND_IVL", sys::NN_REQ_RESEND_IVL),
+            ("NN_SURVEYOR_DEADLINE", sys::NN_SURVEYOR_DEADLINE),
+            ("NN_TCP_NODELAY", sys::NN_TCP_NODELAY),
+            ("NN_WS_MSG_TYPE", sys::NN_WS_MSG_TYPE),
+            ("NN_DONTWAIT", sys::NN_DONTWAIT),
+            ("NN_WS_MSG_TYPE_TEXT", sys::NN_WS_MSG_TYPE_TEXT),
+            ("NN_WS_MSG_TYPE_BINARY", sys::NN_WS_MSG_TYPE_BINARY),
+            ("NN_NS_NAMESPACE", sys::NN_NS_NAMESPACE),
+            ("NN_NS_VERSION", sys::NN_NS_VERSION),
+            ("NN_NS_DOMAIN", sys::NN_NS_DOMAIN),
+            ("NN_NS_TRANSPORT", sys::NN_NS_TRANSPORT),
+            ("NN_NS_PROTOCOL", sys::NN_NS_PROTOCOL),
+            ("NN_NS_OPTION_LEVEL", sys::NN_NS_OPTION_LEVEL),
+            ("NN_NS_SOCKET_OPTION", sys::NN_NS_SOCKET_OPTION),
+            ("NN_NS_TRANSPORT_OPTION", sys::NN_NS_TRANSPORT_OPTION),
+            ("NN_NS_OPTION_TYPE", sys::NN_NS_OPTION_TYPE),
+            ("NN_NS_OPTION_UNIT", sys::NN_NS_OPTION_UNIT),
+            ("NN_NS_FLAG", sys::NN_NS_FLAG),
+            ("NN_NS_ERROR", sys::NN_NS_ERROR),
+            ("NN_NS_LIMIT", sys::NN_NS_LIMIT),
+            ("NN_NS_EVENT", sys::NN_NS_EVENT),
+            ("NN_NS_STATISTIC", sys::NN_NS_STATISTIC),
+            ("NN_TYPE_NONE", sys::NN_TYPE_NONE),
+            ("NN_TYPE_INT", sys::NN_TYPE_INT),
+            ("NN_TYPE_STR", sys::NN_TYPE_STR),
+            ("NN_UNIT_NONE", sys::NN_UNIT_NONE),
+            ("NN_UNIT_BYTES", sys::NN_UNIT_BYTES),
+            ("NN_UNIT_MILLISECONDS", sys::NN_UNIT_MILLISECONDS),
+            ("NN_UNIT_PRIORITY", sys::NN_UNIT_PRIORITY),
+            ("NN_UNIT_BOOLEAN", sys::NN_UNIT_BOOLEAN),
+            ("NN_UNIT_MESSAGES", sys::NN_UNIT_MESSAGES),
+            ("NN_UNIT_COUNTER", sys::NN_UNIT_COUNTER),
+            ("NN_POLLIN", c_int::from(sys::NN_POLLIN)),
+            ("NN_POLLOUT", c_int::from(sys::NN_POLLOUT)),
+            (
+                "NN_STAT_ESTABLISHED_CONNECTIONS",
+                sys::NN_STAT_ESTABLISHED_CONNECTIONS,
+            ),
+            (
+                "NN_STAT_ACCEPTED_CONNECTIONS",
+                sys::NN_STAT_ACCEPTED_CONNECTIONS,
+            ),
+            (
+                "NN_STAT_DROPPED_CONNECTIONS",
+                sys::NN_STAT_DROPPED_CONNECTIONS,
+            ),
+            (
+                "NN_STAT_BROKEN_CONNECTIONS",
+                sys::NN_STAT_BROKEN_CONNECTIONS,
+            ),
+            ("NN_STAT_CONNECT_ERRORS", sys::NN_STAT_CONNECT_ERRORS),
+            ("NN_STAT_BIND_ERRORS", sys::NN_STAT_BIND_ERRORS),
+            ("NN_STAT_ACCEPT_ERRORS", sys::NN_STAT_ACCEPT_ERRORS),
+            (
+                "NN_STAT_CURRENT_CONNECTIONS",
+                sys::NN_STAT_CURRENT_CONNECTIONS,
+            ),
+            (
+                "NN_STAT_INPROGRESS_CONNECTIONS",
+                sys::NN_STAT_INPROGRESS_CONNECTIONS,
+            ),
+            ("NN_STAT_CURRENT_EP_ERRORS", sys::NN_STAT_CURRENT_EP_ERRORS),
+            ("NN_STAT_MESSAGES_SENT", sys::NN_STAT_MESSAGES_SENT),
+            ("NN_STAT_MESSAGES_RECEIVED", sys::NN_STAT_MESSAGES_RECEIVED),
+            ("NN_STAT_BYTES_SENT", sys::NN_STAT_BYTES_SENT),
+            ("NN_STAT_BYTES_RECEIVED", sys::NN_STAT_BYTES_RECEIVED),
+            (
+                "NN_STAT_CURRENT_SND_PRIORITY",
+                sys::NN_STAT_CURRENT_SND_PRIORITY,
+            ),
+            ("EADDRINUSE", sys::EADDRINUSE),
+            ("EADDRNOTAVAIL", sys::EADDRNOTAVAIL),
+            ("EAFNOSUPPORT", sys::EAFNOSUPPORT),
+            ("EAGAIN", sys::EAGAIN),
+            ("EBADF", sys::EBADF),
+            ("ECONNREFUSED", sys::ECONNREFUSED),
+            ("EFAULT", sys::EFAULT),
+            ("EFSM", sys::EFSM),
+            ("EINPROGRESS", sys::EINPROGRESS),
+            ("EINTR", sys::EINTR),
+            ("EINVAL", sys::EINVAL),
+            ("EMFILE", sys::EMFILE),
+            ("ENAMETOOLONG", sys::ENAMETOOLONG),
+            ("ENETDOWN", sys::ENETDOWN),
+            ("ENOBUFS", sys::ENOBUFS),
+            ("ENODEV", sys::ENODEV),
+            ("ENOPROTOOPT", sys::ENOPROTOOPT),
+            ("ENOTSOCK", sys::ENOTSOCK),
+            ("ENOTSUP", sys::ENOTSUP),
+            #[cfg(not(target_os = "openbsd"))]
+            ("EPROTO", sys::EPROTO),
+            ("EPROTONOSUPPORT", sys::EPROTONOSUPPORT),
+            ("ETERM", sys::ETERM),
+            ("ETIMEDOUT", sys::ETIMEDOUT),
+            ("EACCES", sys::EACCESS),
+            ("ECONNABORTED", sys::ECONNABORTED),
+            ("ECONNRESET", sys::ECONNRESET),
+            ("EHOSTUNREACH", sys::EHOSTUNREACH),
+            ("EMSGSIZE", sys::EMSGSIZE),
+            ("ENETRESET", sys::ENETRESET),
+            ("ENETUNREACH", sys::ENETUNREACH),
+            ("ENOTCONN", sys::ENOTCONN),
+        ]
+    }
+
+    /// The feature a constant belongs to, when older releases do not expose it.
+    fn required_feature(name: &str) -> Option<Feature> {
+        if name.starts_with("NN_WS") {
+            Some(Feature::WebSocket)
+        } else if name == "NN_MAXTTL" {
+            Some(Feature::MaxTtl)
+        } else if name == "NN_NS_STATISTIC" || name.starts_with("NN_STAT_") {
+            Some(Feature::Statistics)
+        } else {
+            None
+        }
+    }
+
+    fn symbols_by_name() -> HashMap<&'static str, Symbol> {
+        symbols().map(|symbol| (symbol.name, symbol)).collect()
+    }
+
+    #[test]
+    fn hardcoded_constants_match_the_library() {
+        let symbols = symbols_by_name();
+
+        for &(name, value) in &exported_constants() {
+            if matches!(required_feature(name), Some(feature) if !feature.is_supported()) {
+                continue;
+            }
+
+            match symbols.get(name) {
+                Some(symbol) => assert_eq!(
+                    symbol.value, value,
+                    "constant {} is {} in the library but {} in nanomsg_sys",
+                    name, symbol.value, value
+                ),
+                None => panic!("constant {} is not exposed by the library", name),
+            }
+        }
+    }
+
+    #[test]
+    fn symbols_have_namespace_type_and_unit() {
+        let symbols = symbols_by_name();
+        let linger = symbols["NN_LINGER"];
+        let name = symbols["NN_SOCKET_NAME"];
+
+        assert_eq!(SymbolNamespace::SocketOption, linger.namespace);
+        assert_eq!(SymbolType::Int, linger.value_type);
+        assert_eq!(SymbolUnit::Milliseconds, linger.unit);
+        assert_eq!(SymbolType::Str, name.value_type);
+        assert_eq!(SymbolNamespace::Protocol, symbols["NN_PUSH"].namespace);
+        assert_eq!(SymbolNamespace::Error, symbols["ETERM"].namespace);
+        assert_eq!(SymbolType::None, symbols["ETERM"].value_type);
+    }
+
+    #[test]
+    fn symbols_iteration_ends() {
+        let mut iter = symbols();
+        let count = iter.by_ref().count();
+
+        assert!(count > 0);
+        assert_eq!(None, iter.next());
+    }
+}
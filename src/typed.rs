@@ -0,0 +1,739 @@
+//! Protocol-typed sockets.
+//!
+//! Each type wraps a `Socket` created for a single `Protocol` and only exposes the operations
+//! that make sense for that protocol, so that writing to a `PullSocket` or subscribing on a
+//! `PubSocket` is rejected at compile time instead of failing with `OperationNotSupported`.
+//! Every typed socket can still be converted into the untyped `Socket` for generic code.
+
+use std::convert::TryInto;
+use std::io;
+use std::time::Duration;
+
+use crate::{
+    Address, Domain, Endpoint, Error, Message, PollFd, PollInOut, Protocol, RawMessage, Result,
+    Socket, Statistics, WsMessageType,
+};
+
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+
+#[cfg(windows)]
+use std::os::windows::raw::SOCKET;
+
+/// Typed sockets whose protocol can send messages.
+pub trait SendSocket: Into<Socket> {}
+
+/// Typed sockets whose protocol can receive messages.
+pub trait RecvSocket: Into<Socket> {}
+
+macro_rules! typed_socket(
+    ($(#[$attr:meta])* $name:ident, $protocol:expr) => (
+        $(#[$attr])*
+        pub struct $name {
+            socket: Socket,
+        }
+
+        impl $name {
+            /// Allocates and initializes a new socket, see `Socket::new`.
+            pub fn new() -> Result<$name> {
+                Socket::new($protocol).map(|socket| $name { socket })
+            }
+
+            /// Allocates and initializes a new socket meant to be used in a device, see `Socket::new_for_device`.
+            pub fn new_for_device() -> Result<$name> {
+                Socket::new_for_device($protocol).map(|socket| $name { socket })
+            }
+
+            /// Returns the protocol of the socket.
+            pub fn protocol(&self) -> Protocol {
+                $protocol
+            }
+
+            /// Adds a local endpoint to the socket, see `Socket::bind`.
+            pub fn bind<A>(&mut self, addr: A) -> Result<Endpoint>
+            where
+                A: TryInto<Address>,
+                Error: From<A::Error>,
+            {
+                self.socket.bind(addr)
+            }
+
+            /// Adds a remote endpoint to the socket, see `Socket::connect`.
+            pub fn connect<A>(&mut self, addr: A) -> Result<Endpoint>
+            where
+                A: TryInto<Address>,
+                Error: From<A::Error>,
+            {
+                self.socket.connect(addr)
+            }
+
+            /// See `Socket::endpoints`.
+            pub fn endpoints(&self) -> Vec<Endpoint> {
+                self.socket.endpoints()
+            }
+
+            /// See `Socket::shutdown_address`.
+            pub fn shutdown_address<A>(&mut self, addr: A) -> Result<()>
+            where
+                A: TryInto<Address>,
+                Error: From<A::Error>,
+            {
+                self.socket.shutdown_address(addr)
+            }
+
+            /// See `Socket::shutdown_all`.
+            pub fn shutdown_all(&mut self) -> Result<()> {
+                self.socket.shutdown_all()
+            }
+
+            /// See `Socket::reconcile`.
+            pub fn reconcile(&mut self, addrs: &[Address]) -> Result<()> {
+                self.socket.reconcile(addrs)
+            }
+
+            /// See `Socket::set_linger`.
+            pub fn set_linger(&mut self, linger: isize) -> Result<()> {
+                self.socket.set_linger(linger)
+            }
+
+            /// See `Socket::set_reconnect_interval`.
+            pub fn set_reconnect_interval(&mut self, interval: isize) -> Result<()> {
+                self.socket.set_reconnect_interval(interval)
+            }
+
+            /// See `Socket::set_max_reconnect_interval`.
+            pub fn set_max_reconnect_interval(&mut self, interval: isize) -> Result<()> {
+                self.socket.set_max_reconnect_interval(interval)
+            }
+
+            /// See `Socket::set_ipv4_only`.
+            pub fn set_ipv4_only(&mut self, ipv4_only: bool) -> Result<()> {
+                self.socket.set_ipv4_only(ipv4_only)
+            }
+
+            /// See `Socket::set_socket_name`.
+            #[cfg(not(windows))]
+            pub fn set_socket_name(&mut self, name: &str) -> Result<()> {
+                self.socket.set_socket_name(name)
+            }
+
+            /// See `Socket::get_socket_name`.
+            #[cfg(not(windows))]
+            pub fn get_socket_name(&mut self, len: usize) -> Result<String> {
+                self.socket.get_socket_name(len)
+            }
+
+            /// See `Socket::set_linger_duration`.
+            pub fn set_linger_duration(&mut self, linger: Option<Duration>) -> Result<()> {
+                self.socket.set_linger_duration(linger)
+            }
+
+            /// See `Socket::get_linger_duration`.
+            pub fn get_linger_duration(&self) -> Result<Option<Duration>> {
+                self.socket.get_linger_duration()
+            }
+
+            /// See `Socket::set_reconnect_interval_duration`.
+            pub fn set_reconnect_interval_duration(&mut self, interval: Duration) -> Result<()> {
+                self.socket.set_reconnect_interval_duration(interval)
+            }
+
+            /// See `Socket::get_reconnect_interval_duration`.
+            pub fn get_reconnect_interval_duration(&self) -> Result<Duration> {
+                self.socket.get_reconnect_interval_duration()
+            }
+
+            /// See `Socket::set_max_reconnect_interval_duration`.
+            pub fn set_max_reconnect_interval_duration(&mut self, interval: Duration) -> Result<()> {
+                self.socket.set_max_reconnect_interval_duration(interval)
+            }
+
+            /// See `Socket::get_max_reconnect_interval_duration`.
+            pub fn get_max_reconnect_interval_duration(&self) -> Result<Duration> {
+                self.socket.get_max_reconnect_interval_duration()
+            }
+
+            /// See `Socket::get_linger`.
+            pub fn get_linger(&self) -> Result<isize> {
+                self.socket.get_linger()
+            }
+
+            /// See `Socket::get_reconnect_interval`.
+            pub fn get_reconnect_interval(&self) -> Result<isize> {
+                self.socket.get_reconnect_interval()
+            }
+
+            /// See `Socket::get_max_reconnect_interval`.
+            pub fn get_max_reconnect_interval(&self) -> Result<isize> {
+                self.socket.get_max_reconnect_interval()
+            }
+
+            /// See `Socket::get_ipv4_only`.
+            pub fn get_ipv4_only(&self) -> Result<bool> {
+                self.socket.get_ipv4_only()
+            }
+
+            /// See `Socket::set_max_ttl`.
+            pub fn set_max_ttl(&mut self, ttl: u8) -> Result<()> {
+                self.socket.set_max_ttl(ttl)
+            }
+
+            /// See `Socket::get_max_ttl`.
+            pub fn get_max_ttl(&self) -> Result<u8> {
+                self.socket.get_max_ttl()
+            }
+
+            /// See `Socket::statistics`.
+            pub fn statistics(&self) -> Result<Statistics> {
+                self.socket.statistics()
+            }
+
+            /// See `Socket::get_domain`.
+            pub fn get_domain(&self) -> Result<Domain> {
+                self.socket.get_domain()
+            }
+
+            /// See `Socket::set_tcp_nodelay`.
+            pub fn set_tcp_nodelay(&mut self, tcp_nodelay: bool) -> Result<()> {
+                self.socket.set_tcp_nodelay(tcp_nodelay)
+            }
+
+            /// See `Socket::set_ws_msg_type`.
+            pub fn set_ws_msg_type(&mut self, msg_type: WsMessageType) -> Result<()> {
+                self.socket.set_ws_msg_type(msg_type)
+            }
+
+            /// See `Socket::get_ws_msg_type`.
+            pub fn get_ws_msg_type(&self) -> Result<WsMessageType> {
+                self.socket.get_ws_msg_type()
+            }
+
+            /// Creates a poll request for the socket, see `Socket::new_pollfd`.
+            pub fn new_pollfd(&self, pollinout: PollInOut) -> PollFd {
+                self.socket.new_pollfd(pollinout)
+            }
+
+            /// Converts the typed socket into the untyped `Socket`.
+            pub fn into_socket(self) -> Socket {
+                self.socket
+            }
+        }
+
+        impl From<$name> for Socket {
+            fn from(typed: $name) -> Socket {
+                typed.socket
+            }
+        }
+
+        /// Registers the socket in a `mio` event loop, see the `mio::event::Source` implementation of `Socket`.
+        #[cfg(all(feature = "mio", unix))]
+        impl mio::event::Source for $name {
+            fn register(
+                &mut self,
+                registry: &mio::Registry,
+                token: mio::Token,
+                interests: mio::Interest,
+            ) -> std::io::Result<()> {
+                mio::event::Source::register(&mut self.socket, registry, token, interests)
+            }
+
+            fn reregister(
+                &mut self,
+                registry: &mio::Registry,
+                token: mio::Token,
+                interests: mio::Interest,
+            ) -> std::io::Result<()> {
+                mio::event::Source::reregister(&mut self.socket, registry, token, interests)
+            }
+
+            fn deregister(&mut self, registry: &mio::Registry) -> std::io::Result<()> {
+                mio::event::Source::deregister(&mut self.socket, registry)
+            }
+        }
+    )
+);
+
+macro_rules! send_socket(
+    ($name:ident) => (
+        impl SendSocket for $name {}
+
+        impl $name {
+            /// Non-blocking version of the `write` function, see `Socket::nb_write`.
+            pub fn nb_write(&self, buf: &[u8]) -> Result<usize> {
+                self.socket.nb_write(buf)
+            }
+
+            /// Zero-copy version of the `write` function, see `Socket::zc_write`.
+            pub fn zc_write(&self, buf: &[u8]) -> Result<usize> {
+                self.socket.zc_write(buf)
+            }
+
+            /// Sends a message allocated by nanomsg without copying it, see `Socket::send_msg`.
+            pub fn send_msg(&self, msg: Message) -> Result<usize> {
+                self.socket.send_msg(msg)
+            }
+
+            /// Sends a single message made of the specified buffers, see `Socket::send_vectored`.
+            pub fn send_vectored(&self, bufs: &[io::IoSlice]) -> Result<usize> {
+                self.socket.send_vectored(bufs)
+            }
+
+            /// Sends a message together with its SP header, on a socket created with `new_for_device`,
+            /// see `Socket::send_raw_msg`.
+            pub fn send_raw_msg(&self, msg: RawMessage) -> Result<usize> {
+                self.socket.send_raw_msg(msg)
+            }
+
+            /// See `Socket::set_send_buffer_size`.
+            pub fn set_send_buffer_size(&mut self, size_in_bytes: usize) -> Result<()> {
+                self.socket.set_send_buffer_size(size_in_bytes)
+            }
+
+            /// See `Socket::set_send_timeout`.
+            pub fn set_send_timeout(&mut self, timeout: isize) -> Result<()> {
+                self.socket.set_send_timeout(timeout)
+            }
+
+            /// See `Socket::set_send_priority`.
+            pub fn set_send_priority(&mut self, priority: u8) -> Result<()> {
+                self.socket.set_send_priority(priority)
+            }
+
+            /// See `Socket::set_send_timeout_duration`.
+            pub fn set_send_timeout_duration(&mut self, timeout: Option<Duration>) -> Result<()> {
+                self.socket.set_send_timeout_duration(timeout)
+            }
+
+            /// See `Socket::get_send_timeout_duration`.
+            pub fn get_send_timeout_duration(&self) -> Result<Option<Duration>> {
+                self.socket.get_send_timeout_duration()
+            }
+
+            /// See `Socket::get_send_buffer_size`.
+            pub fn get_send_buffer_size(&self) -> Result<usize> {
+                self.socket.get_send_buffer_size()
+            }
+
+            /// See `Socket::get_send_timeout`.
+            pub fn get_send_timeout(&self) -> Result<isize> {
+                self.socket.get_send_timeout()
+            }
+
+            /// See `Socket::get_send_priority`.
+            pub fn get_send_priority(&self) -> Result<u8> {
+                self.socket.get_send_priority()
+            }
+
+            /// See `Socket::get_send_fd`.
+            #[cfg(unix)]
+            pub fn get_send_fd(&mut self) -> Result<RawFd> {
+                self.socket.get_send_fd()
+            }
+
+            /// See `Socket::get_send_fd`.
+            #[cfg(windows)]
+            pub fn get_send_fd(&mut self) -> Result<SOCKET> {
+                self.socket.get_send_fd()
+            }
+        }
+
+        impl io::Write for $name {
+            /// Sends a message containing the data from the buffer, see `Socket::write`.
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.socket.write(buf)
+            }
+
+            fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+                self.socket.write_vectored(bufs)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.socket.flush()
+            }
+        }
+    )
+);
+
+macro_rules! recv_socket(
+    ($name:ident) => (
+        impl RecvSocket for $name {}
+
+        impl $name {
+            /// Non-blocking version of the `read` function, see `Socket::nb_read`.
+            pub fn nb_read(&self, buf: &mut [u8]) -> Result<usize> {
+                self.socket.nb_read(buf)
+            }
+
+            /// Non-blocking version of the `read_to_end` function, see `Socket::nb_read_to_end`.
+            pub fn nb_read_to_end(&self, buf: &mut Vec<u8>) -> Result<usize> {
+                self.socket.nb_read_to_end(buf)
+            }
+
+            /// Receives a message allocated by nanomsg, see `Socket::recv_msg`.
+            pub fn recv_msg(&self) -> Result<Message> {
+                self.socket.recv_msg()
+            }
+
+            /// Non-blocking version of the `recv_msg` function, see `Socket::nb_recv_msg`.
+            pub fn nb_recv_msg(&self) -> Result<Message> {
+                self.socket.nb_recv_msg()
+            }
+
+            /// Receives a message together with its SP header, on a socket created with `new_for_device`,
+            /// see `Socket::recv_raw_msg`.
+            pub fn recv_raw_msg(&self) -> Result<RawMessage> {
+                self.socket.recv_raw_msg()
+            }
+
+            /// Receives a message and scatters it into the specified buffers, see `Socket::recv_vectored`.
+            pub fn recv_vectored(&self, bufs: &mut [io::IoSliceMut]) -> Result<usize> {
+                self.socket.recv_vectored(bufs)
+            }
+
+            /// See `Socket::set_receive_buffer_size`.
+            pub fn set_receive_buffer_size(&mut self, size_in_bytes: usize) -> Result<()> {
+                self.socket.set_receive_buffer_size(size_in_bytes)
+            }
+
+            /// See `Socket::set_receive_max_size`.
+            pub fn set_receive_max_size(&mut self, size_in_bytes: isize) -> Result<()> {
+                self.socket.set_receive_max_size(size_in_bytes)
+            }
+
+            /// See `Socket::set_receive_timeout`.
+            pub fn set_receive_timeout(&mut self, timeout: isize) -> Result<()> {
+                self.socket.set_receive_timeout(timeout)
+            }
+
+            /// See `Socket::set_receive_priority`.
+            pub fn set_receive_priority(&mut self, priority: u8) -> Result<()> {
+                self.socket.set_receive_priority(priority)
+            }
+
+            /// See `Socket::set_receive_timeout_duration`.
+            pub fn set_receive_timeout_duration(&mut self, timeout: Option<Duration>) -> Result<()> {
+                self.socket.set_receive_timeout_duration(timeout)
+            }
+
+            /// See `Socket::get_receive_timeout_duration`.
+            pub fn get_receive_timeout_duration(&self) -> Result<Option<Duration>> {
+                self.socket.get_receive_timeout_duration()
+            }
+
+            /// See `Socket::get_receive_buffer_size`.
+            pub fn get_receive_buffer_size(&self) -> Result<usize> {
+                self.socket.get_receive_buffer_size()
+            }
+
+            /// See `Socket::get_receive_max_size`.
+            pub fn get_receive_max_size(&self) -> Result<isize> {
+                self.socket.get_receive_max_size()
+            }
+
+            /// See `Socket::get_receive_timeout`.
+            pub fn get_receive_timeout(&self) -> Result<isize> {
+                self.socket.get_receive_timeout()
+            }
+
+            /// See `Socket::get_receive_priority`.
+            pub fn get_receive_priority(&self) -> Result<u8> {
+                self.socket.get_receive_priority()
+            }
+
+            /// See `Socket::get_receive_fd`.
+            #[cfg(unix)]
+            pub fn get_receive_fd(&mut self) -> Result<RawFd> {
+                self.socket.get_receive_fd()
+            }
+
+            /// See `Socket::get_receive_fd`.
+            #[cfg(windows)]
+            pub fn get_receive_fd(&mut self) -> Result<SOCKET> {
+                self.socket.get_receive_fd()
+            }
+        }
+
+        impl io::Read for $name {
+            /// Receives a message into the buffer, see `Socket::read`.
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.socket.read(buf)
+            }
+
+            fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut]) -> io::Result<usize> {
+                self.socket.read_vectored(bufs)
+            }
+
+            fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+                self.socket.read_to_end(buf)
+            }
+
+            fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+                self.socket.read_to_string(buf)
+            }
+        }
+    )
+);
+
+typed_socket!(
+    /// Client side of the request/reply protocol, see `Protocol::Req`.
+    ReqSocket,
+    Protocol::Req
+);
+send_socket!(ReqSocket);
+recv_socket!(ReqSocket);
+
+impl ReqSocket {
+    /// See `Socket::set_request_resend_interval`.
+    pub fn set_request_resend_interval(&mut self, interval: isize) -> Result<()> {
+        self.socket.set_request_resend_interval(interval)
+    }
+
+    /// See `Socket::set_request_resend_interval_duration`.
+    pub fn set_request_resend_interval_duration(&mut self, interval: Duration) -> Result<()> {
+        self.socket.set_request_resend_interval_duration(interval)
+    }
+
+    /// See `Socket::get_request_resend_interval_duration`.
+    pub fn get_request_resend_interval_duration(&self) -> Result<Duration> {
+        self.socket.get_request_resend_interval_duration()
+    }
+}
+
+typed_socket!(
+    /// Server side of the request/reply protocol, see `Protocol::Rep`.
+    RepSocket,
+    Protocol::Rep
+);
+send_socket!(RepSocket);
+recv_socket!(RepSocket);
+
+typed_socket!(
+    /// Sending side of the pipeline protocol, see `Protocol::Push`.
+    PushSocket,
+    Protocol::Push
+);
+send_socket!(PushSocket);
+
+typed_socket!(
+    /// Receiving side of the pipeline protocol, see `Protocol::Pull`.
+    PullSocket,
+    Protocol::Pull
+);
+recv_socket!(PullSocket);
+
+typed_socket!(
+    /// One-to-one bidirectional socket, see `Protocol::Pair`.
+    PairSocket,
+    Protocol::Pair
+);
+send_socket!(PairSocket);
+recv_socket!(PairSocket);
+
+typed_socket!(
+    /// Many-to-many bidirectional socket, see `Protocol::Bus`.
+    BusSocket,
+    Protocol::Bus
+);
+send_socket!(BusSocket);
+recv_socket!(BusSocket);
+
+typed_socket!(
+    /// Publishing side of the publish/subscribe protocol, see `Protocol::Pub`.
+    PubSocket,
+    Protocol::Pub
+);
+send_socket!(PubSocket);
+
+typed_socket!(
+    /// Subscribing side of the publish/subscribe protocol, see `Protocol::Sub`.
+    SubSocket,
+    Protocol::Sub
+);
+recv_socket!(SubSocket);
+
+impl SubSocket {
+    /// Subscribes for a particular topic, see `Socket::subscribe`.
+    pub fn subscribe(&mut self, topic: &[u8]) -> Result<()> {
+        self.socket.subscribe(topic)
+    }
+
+    /// Unsubscribes from a particular topic, see `Socket::unsubscribe`.
+    pub fn unsubscribe(&mut self, topic: &[u8]) -> Result<()> {
+        self.socket.unsubscribe(topic)
+    }
+}
+
+typed_socket!(
+    /// Sending side of the survey protocol, see `Protocol::Surveyor`.
+    SurveyorSocket,
+    Protocol::Surveyor
+);
+send_socket!(SurveyorSocket);
+recv_socket!(SurveyorSocket);
+
+impl SurveyorSocket {
+    /// See `Socket::set_survey_deadline`.
+    pub fn set_survey_deadline(&mut self, deadline: isize) -> Result<()> {
+        self.socket.set_survey_deadline(deadline)
+    }
+
+    /// See `Socket::set_survey_deadline_duration`.
+    pub fn set_survey_deadline_duration(&mut self, deadline: Duration) -> Result<()> {
+        self.socket.set_survey_deadline_duration(deadline)
+    }
+
+    /// See `Socket::get_survey_deadline_duration`.
+    pub fn get_survey_deadline_duration(&self) -> Result<Duration> {
+        self.socket.get_survey_deadline_duration()
+    }
+}
+
+typed_socket!(
+    /// Responding side of the survey protocol, see `Protocol::Respondent`.
+    RespondentSocket,
+    Protocol::Respondent
+);
+send_socket!(RespondentSocket);
+recv_socket!(RespondentSocket);
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_must_use)]
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    fn test_read<R: Read>(socket: &mut R, expected: &[u8]) {
+        let mut buf = [0u8; 6];
+        match socket.read(&mut buf) {
+            Ok(len) => {
+                assert_eq!(len, 6);
+                assert_eq!(buf.as_ref(), expected)
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn typed_pipeline() {
+        let url = "ipc:///tmp/typed_pipeline.ipc";
+
+        let mut push_socket = PushSocket::new().unwrap();
+        push_socket.bind(url).unwrap();
+
+        let mut pull_socket = PullSocket::new().unwrap();
+        pull_socket.connect(url).unwrap();
+
+        thread::sleep(Duration::from_millis(10));
+
+        push_socket.write_all(b"foobar").unwrap();
+        test_read(&mut pull_socket, b"foobar");
+    }
+
+    #[test]
+    fn typed_pubsub() {
+        let url = "ipc:///tmp/typed_pubsub.ipc";
+
+        let mut pub_socket = PubSocket::new().unwrap();
+        pub_socket.bind(url).unwrap();
+
+        let mut sub_socket = SubSocket::new().unwrap();
+        sub_socket.subscribe(b"foo").unwrap();
+        sub_socket.connect(url).unwrap();
+
+        thread::sleep(Duration::from_millis(150));
+
+        pub_socket.write_all(b"foobar").unwrap();
+        test_read(&mut sub_socket, b"foobar");
+    }
+
+    #[test]
+    fn typed_reqrep() {
+        let url = "ipc:///tmp/typed_reqrep.ipc";
+
+        let mut server = RepSocket::new().unwrap();
+        server.bind(url).unwrap();
+
+        let mut client = ReqSocket::new().unwrap();
+        client.set_request_resend_interval(60000).unwrap();
+        client.connect(url).unwrap();
+
+        thread::sleep(Duration::from_millis(150));
+
+        client.write_all(b"I WANT").unwrap();
+        test_read(&mut server, b"I WANT");
+
+        server.write_all(b"I GIVE").unwrap();
+        test_read(&mut client, b"I GIVE");
+    }
+
+    #[test]
+    fn typed_socket_reports_its_protocol() {
+        macro_rules! check_protocol(
+            ($($name:ident),*) => ($(
+                let socket = $name::new().unwrap();
+                let protocol = socket.protocol();
+
+                assert_eq!(Ok(protocol), socket.into_socket().get_protocol());
+            )*)
+        );
+
+        check_protocol!(
+            ReqSocket,
+            RepSocket,
+            PushSocket,
+            PullSocket,
+            PairSocket,
+            BusSocket,
+            PubSocket,
+            SubSocket,
+            SurveyorSocket,
+            RespondentSocket
+        );
+    }
+
+    #[test]
+    fn typed_socket_can_be_converted_to_socket() {
+        let url = "ipc:///tmp/typed_socket_can_be_converted_to_socket.ipc";
+
+        let mut pair_socket = PairSocket::new().unwrap();
+        pair_socket.bind(url).unwrap();
+
+        let mut socket: Socket = PairSocket::new().unwrap().into();
+        socket.connect(url).unwrap();
+
+        thread::sleep(Duration::from_millis(10));
+
+        socket.write_all(b"foobar").unwrap();
+        test_read(&mut pair_socket, b"foobar");
+    }
+
+    #[test]
+    fn typed_device_socket_can_route_reply_with_backtrace() {
+        let url = "ipc:///tmp/typed_device_socket_can_route_reply_with_backtrace.ipc";
+
+        let mut server = RepSocket::new_for_device().unwrap();
+        server.bind(url).unwrap();
+
+        let mut client = ReqSocket::new().unwrap();
+        client.connect(url).unwrap();
+
+        thread::sleep(Duration::from_millis(10));
+
+        client.write_all(b"foobar").unwrap();
+
+        let request = server.recv_raw_msg().unwrap();
+        let backtrace = request.backtrace().unwrap();
+        assert_eq!(b"foobar", &request.body()[..]);
+
+        let reply = RawMessage::new(
+            backtrace.to_bytes(),
+            Message::from_slice(b"barfoo").unwrap(),
+        );
+        server.send_raw_msg(reply).unwrap();
+
+        test_read(&mut client, b"barfoo");
+    }
+}
@@ -0,0 +1,183 @@
+//! Detection of the version and features of the linked nanomsg library.
+//!
+//! The crate can be built against any nanomsg installation found by pkg-config, or the bundled one.
+//! Methods relying on features missing from older releases check them at runtime
+//! and fail with `Error::Unsupported` instead of an obscure error or a missing symbol.
+
+use libc::c_int;
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::result::{Error, Result};
+use crate::symbol::{symbols, SymbolNamespace};
+
+/// The version of the linked nanomsg library.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Version {
+    /// The release detected when building the crate, `None` if it could not be detected.
+    /// The library loaded at runtime may differ when linking dynamically.
+    pub release: Option<&'static str>,
+    /// The ABI version of the library, as reported by its `NN_VERSION_CURRENT` symbol.
+    pub current: c_int,
+    /// The revision of the ABI version, `NN_VERSION_REVISION`.
+    pub revision: c_int,
+    /// The number of previous ABI versions the library is compatible with, `NN_VERSION_AGE`.
+    pub age: c_int,
+}
+
+impl fmt::Display for Version {
+    /// Formats the version as `<release> (ABI <current>.<revision>.<age>)`.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} (ABI {}.{}.{})",
+            self.release.unwrap_or("unknown"),
+            self.current,
+            self.revision,
+            self.age
+        )
+    }
+}
+
+/// Features that are not available in every nanomsg release.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Feature {
+    /// The `ws://` transport and its `NN_WS_MSG_TYPE` option.
+    WebSocket,
+    /// Socket statistics, see `Socket::statistics`.
+    Statistics,
+    /// The `NN_MAXTTL` socket option, see `Socket::set_max_ttl`.
+    MaxTtl,
+}
+
+impl Feature {
+    /// The symbol the library exposes when it supports the feature.
+    fn symbol(self) -> &'static str {
+        match self {
+            Feature::WebSocket => "NN_WS",
+            Feature::Statistics => "NN_STAT_MESSAGES_SENT",
+            Feature::MaxTtl => "NN_MAXTTL",
+        }
+    }
+
+    /// Tells whether the linked library supports the feature.
+    pub fn is_supported(self) -> bool {
+        detected().features.contains(&self)
+    }
+}
+
+const FEATURES: [Feature; 3] = [Feature::WebSocket, Feature::Statistics, Feature::MaxTtl];
+
+struct Detected {
+    version: Version,
+    features: Vec<Feature>,
+}
+
+/// Scans the symbols of the library once, they cannot change while the process runs.
+fn detected() -> &'static Detected {
+    static DETECTED: OnceLock<Detected> = OnceLock::new();
+
+    DETECTED.get_or_init(|| {
+        let mut version = Version {
+            release: nanomsg_sys::NANOMSG_VERSION,
+            current: 0,
+            revision: 0,
+            age: 0,
+        };
+        let mut features = Vec::new();
+
+        for symbol in symbols() {
+            if symbol.namespace == SymbolNamespace::Version {
+                match symbol.name {
+                    "NN_VERSION_CURRENT" => version.current = symbol.value,
+                    "NN_VERSION_REVISION" => version.revision = symbol.value,
+                    "NN_VERSION_AGE" => version.age = symbol.value,
+                    _ => {}
+                }
+            }
+
+            for feature in FEATURES.iter() {
+                if symbol.name == feature.symbol() {
+                    features.push(*feature);
+                }
+            }
+        }
+
+        Detected { version, features }
+    })
+}
+
+/// Returns the version of the linked nanomsg library.
+///
+/// # Example
+///
+/// ```rust
+/// let version = nanomsg::version();
+///
+/// println!("linked against nanomsg {}", version);
+/// ```
+pub fn version() -> Version {
+    detected().version
+}
+
+/// Fails with `Error::Unsupported` when the linked library does not support the feature.
+pub(crate) fn require(feature: Feature) -> Result<()> {
+    if feature.is_supported() {
+        Ok(())
+    } else {
+        Err(Error::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{require, version, Feature, Version};
+
+    #[test]
+    fn version_can_be_displayed() {
+        let version = Version {
+            release: Some("1.1.4"),
+            current: 5,
+            revision: 1,
+            age: 0,
+        };
+
+        assert_eq!("1.1.4 (ABI 5.1.0)", version.to_string());
+    }
+
+    #[test]
+    fn version_is_read_from_the_library() {
+        let version = version();
+
+        assert!(version.current > 0);
+        assert_eq!(nanomsg_sys::NANOMSG_VERSION, version.release);
+    }
+
+    /// Extracts the major and minor numbers of the release, like the `nanomsg-sys` build script.
+    fn major_minor(release: &str) -> Option<(u32, u32)> {
+        let mut numbers = release.split(|c: char| !c.is_ascii_digit());
+        let major = numbers.next()?.parse().ok()?;
+        let minor = numbers.next()?.parse().ok()?;
+
+        Some((major, minor))
+    }
+
+    #[test]
+    fn features_of_recent_libraries_are_supported() {
+        let release = match version().release.and_then(major_minor) {
+            Some(release) => release,
+            None => return,
+        };
+
+        if release >= (1, 0) {
+            assert!(Feature::WebSocket.is_supported());
+            assert!(Feature::Statistics.is_supported());
+            assert_eq!(Ok(()), require(Feature::MaxTtl));
+        }
+        if release < (0, 5) {
+            assert!(!Feature::Statistics.is_supported());
+            assert!(require(Feature::Statistics).is_err());
+        }
+    }
+}